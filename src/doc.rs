@@ -1,13 +1,64 @@
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use std::{fs, io};
 
 use reqwest::blocking::multipart;
+use reqwest::StatusCode;
 
 use super::{Error, Result};
 use crate::{builder, DeepL, Formality, Language};
 
+/// Counting semaphore limiting the number of concurrent document uploads
+pub(crate) struct Semaphore {
+    limit: usize,
+    permits: Mutex<usize>,
+    released: Condvar,
+}
+
+/// A permit held for the duration of an upload, released on drop
+struct Permit<'a>(&'a Semaphore);
+
+impl Semaphore {
+    /// Creates a semaphore with the given number of permits
+    pub(crate) fn new(permits: usize) -> Self {
+        Self {
+            limit: permits,
+            permits: Mutex::new(permits),
+            released: Condvar::new(),
+        }
+    }
+
+    /// The total number of permits
+    pub(crate) fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Blocks until a permit is available
+    fn acquire(&self) -> Permit<'_> {
+        let mut permits = self.permits.lock().unwrap_or_else(|e| e.into_inner());
+        while *permits == 0 {
+            permits = self
+                .released
+                .wait(permits)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        *permits -= 1;
+        Permit(self)
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        *self.0.permits.lock().unwrap_or_else(|e| e.into_inner()) += 1;
+        self.0.released.notify_one();
+    }
+}
+
 /// Document handle
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub struct Document {
     /// A unique ID assigned to the uploaded document
     pub document_id: String,
@@ -15,8 +66,31 @@ pub struct Document {
     pub document_key: String,
 }
 
+impl Document {
+    /// Creates a handle from the id and key of a previously uploaded document, e.g. to
+    /// check its status or download it after a restart.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::*;
+    /// # let dl = DeepL::new(&std::env::var("DEEPL_API_KEY").unwrap());
+    /// let doc = Document::new(
+    ///     "04DE5AD98A02647D83285A36021911C6".to_string(),
+    ///     "0CB0054F1C132C1625B392EEDE".to_string(),
+    /// );
+    /// let status = dl.document_status(&doc).unwrap();
+    /// ```
+    pub fn new(document_id: String, document_key: String) -> Self {
+        Self {
+            document_id,
+            document_key,
+        }
+    }
+}
+
 /// Document translation status
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct DocumentStatus {
     /// A unique ID assigned to the uploaded document
     pub document_id: String,
@@ -24,16 +98,19 @@ pub struct DocumentStatus {
     pub status: DocState,
     /// Estimated number of seconds until the translation is done.
     /// This parameter is only included while status is "translating".
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub seconds_remaining: Option<u64>,
     /// The number of characters billed to your account
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub billed_characters: Option<u64>,
     /// Description of the error, if available.
     /// This parameter may be included if an error occurred during translation.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub error_message: Option<String>,
 }
 
 /// Document state
-#[derive(Clone, Copy, Debug, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum DocState {
     /// The translation job is waiting in line to be processed
@@ -58,6 +135,11 @@ builder! {
             filename: String,
             formality: Formality,
             glossary_id: String,
+            output_format: String,
+            enable_document_minification: bool,
+        };
+        @private{
+            file_bytes: (Arc<[u8]>, String),
         };
     }
 }
@@ -67,33 +149,97 @@ impl DocumentStatus {
     pub fn is_done(&self) -> bool {
         matches!(self.status, DocState::Done)
     }
+
+    /// Whether an irrecoverable error occurred while translating the document
+    pub fn is_error(&self) -> bool {
+        matches!(self.status, DocState::Error)
+    }
+
+    /// Whether the document is waiting in line to be translated
+    pub fn is_queued(&self) -> bool {
+        matches!(self.status, DocState::Queued)
+    }
+
+    /// Estimated time until the translation is done. Only available while the document
+    /// is translating, so this returns `None` while queued.
+    pub fn estimated_remaining(&self) -> Option<Duration> {
+        self.seconds_remaining.map(Duration::from_secs)
+    }
 }
 
 impl DocumentOptions {
+    /// Construct a new `DocumentOptions` from an in-memory document.
+    ///
+    /// The `filename` is required, since DeepL infers the document type from its extension.
+    /// The bytes are shared between upload attempts rather than copied for each one.
+    #[must_use]
+    pub fn from_bytes(target_lang: Language, bytes: Vec<u8>, filename: String) -> Self {
+        let mut opt = Self::new(target_lang, PathBuf::from(&filename)).filename(filename.clone());
+        opt.file_bytes = Some((bytes.into(), filename));
+        opt
+    }
+
+    /// Checks options the server would reject
+    fn validate(&self) -> Result<()> {
+        if self.glossary_id.is_some() && self.source_lang.is_none() {
+            return Err(Error::Client(
+                "glossary_id requires source_lang to be set".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
     /// Creates a multipart request form from an instance of `DocumentOptions`
     fn into_multipart(self) -> Result<multipart::Form> {
+        let file = match self.file_bytes {
+            Some((bytes, name)) => {
+                let len = bytes.len() as u64;
+                multipart::Part::reader_with_length(io::Cursor::new(bytes), len).file_name(name)
+            }
+            None => multipart::Part::file(self.file_path)?,
+        };
+
         let mut form = multipart::Form::new()
-            .file("file", self.file_path)
-            .map_err(|_| Error::Client("failed to attach file".to_string()))?
+            .part("file", file)
             .text("target_lang", self.target_lang.to_string());
 
         if let Some(src) = self.source_lang {
-            form = form.text("source_lang", src.to_string());
+            form = form.text("source_lang", src.base().to_string());
         }
         if let Some(name) = self.filename {
             form = form.text("filename", name);
         }
-        if let Some(fm) = self.formality {
+        if let Some(fm) = self
+            .formality
+            .and_then(|fm| fm.for_target(self.target_lang))
+        {
             form = form.text("formality", fm.as_ref().to_string());
         }
         if let Some(glos) = self.glossary_id {
             form = form.text("glossary_id", glos);
         }
+        if let Some(fmt) = self.output_format {
+            form = form.text("output_format", fmt);
+        }
+        if let Some(minify) = self.enable_document_minification {
+            form = form.text("enable_document_minification", minify.to_string());
+        }
 
         Ok(form)
     }
 }
 
+/// Whether the error occurred while sending a request, before any response was received,
+/// e.g. the connection was refused or timed out
+fn is_send_error(e: &Error) -> bool {
+    match e {
+        // A connection dropped after the body was sent (`is_request`) is not retried,
+        // since the server may already have accepted the upload
+        Error::Reqwest(e) => e.status().is_none() && (e.is_connect() || e.is_timeout()),
+        _ => false,
+    }
+}
+
 impl DeepL {
     /// POST /document
     ///
@@ -105,6 +251,26 @@ impl DeepL {
     /// and finally fetching the translation result with
     /// [`document_download`](Self::document_download).
     ///
+    /// If the upload fails before a response is received, e.g. because the connection
+    /// was refused or timed out, it is retried up to the number of times set with
+    /// [`set_max_retries`](Self::set_max_retries).
+    ///
+    /// If a limit was set with
+    /// [`set_max_concurrent_documents`](Self::set_max_concurrent_documents), this blocks
+    /// until fewer than that many uploads are in progress.
+    ///
+    /// Note: the document endpoint accepts fewer options than text translation. Formatting
+    /// options such as `preserve_formatting`, `split_sentences`, and `tag_handling` are not
+    /// supported for documents, which keep the formatting of the original file.
+    ///
+    /// As with [`translate`](Self::translate), a formality of `More` or `Less` is not sent
+    /// for a target language that doesn't support formality.
+    ///
+    /// ## Errors
+    ///
+    /// If `glossary_id` is set without `source_lang`, since DeepL requires the source
+    /// language when using a glossary.
+    ///
     /// ## Example
     ///
     /// ```rust,no_run
@@ -123,26 +289,85 @@ impl DeepL {
     /// }
     ///
     /// let out_file = PathBuf::from("test-translated.txt");
-    /// let _ = dl.document_download(doc, Some(out_file.clone())).unwrap();
+    /// let _ = dl.document_download(&doc, Some(out_file.clone())).unwrap();
     /// let content = fs::read_to_string(out_file).unwrap();
     /// assert!(!content.is_empty());
     /// ```
+    ///
+    /// By default the translated document has the same format as the input. Set
+    /// `output_format` to the file extension of another format to convert it, e.g.
+    /// `"pdf"` for a `.docx` input or `"docx"` for a `.pdf` input. DeepL rejects
+    /// unsupported conversions with a [`Server`](Error::Server) error.
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::*;
+    /// # use std::{env, path::PathBuf};
+    /// # let dl = DeepL::new(&env::var("DEEPL_API_KEY").unwrap());
+    /// let opt = DocumentOptions::new(Language::DE, PathBuf::from("report.docx"))
+    ///     .output_format("pdf".to_string());
+    /// let doc = dl.document_upload(opt).unwrap();
+    /// ```
     pub fn document_upload(&self, opt: DocumentOptions) -> Result<Document> {
-        let url = format!("{}/document", self.url);
+        self.document_upload_inner(opt, None)
+    }
 
-        let form = opt.into_multipart()?;
+    /// POST /document
+    ///
+    /// Like [`document_upload`](Self::document_upload), but with a `timeout` applied to
+    /// this request only, overriding any timeout set on the client.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::*;
+    /// # use std::{env, path::PathBuf, time::Duration};
+    /// # let dl = DeepL::new(&env::var("DEEPL_API_KEY").unwrap());
+    /// let opt = DocumentOptions::new(Language::DE, PathBuf::from("large.pdf"));
+    /// let doc = dl
+    ///     .document_upload_with_timeout(opt, Duration::from_secs(300))
+    ///     .unwrap();
+    /// ```
+    pub fn document_upload_with_timeout(
+        &self,
+        opt: DocumentOptions,
+        timeout: Duration,
+    ) -> Result<Document> {
+        self.document_upload_inner(opt, Some(timeout))
+    }
 
-        let resp = self
-            .post(url)
-            .multipart(form)
-            .send()
-            .map_err(Error::Reqwest)?;
+    /// Uploads a document, optionally with a per-request timeout
+    fn document_upload_inner(
+        &self,
+        opt: DocumentOptions,
+        timeout: Option<Duration>,
+    ) -> Result<Document> {
+        opt.validate()?;
+
+        if self.dry_run {
+            // the file is still read, to surface errors the real upload would hit
+            opt.into_multipart()?;
+            return Ok(Document::new("dry-run".to_string(), "dry-run".to_string()));
+        }
+
+        let url = self.endpoint(&["document"]);
+        let _permit = self.document_slots.as_ref().map(Semaphore::acquire);
+
+        // The multipart body is consumed when sent, so it is rebuilt (re-reading
+        // the file) on each attempt
+        let resp = self.retry(is_send_error, || {
+            let form = opt.clone().into_multipart()?;
+            let mut req = self.post(url.clone()).multipart(form);
+            if let Some(timeout) = timeout {
+                req = req.timeout(timeout);
+            }
+            self.send(req)
+        })?;
 
         if !resp.status().is_success() {
             return super::convert(resp);
         }
 
-        resp.json().map_err(|_| Error::Deserialize)
+        resp.json().map_err(Error::deserialize)
     }
 
     /// POST /document/`{document_id}`
@@ -151,23 +376,18 @@ impl DeepL {
     /// [`DocumentStatus`] contains a field `error_message` that may provide context
     /// for the cause of the error.
     pub fn document_status(&self, doc: &Document) -> Result<DocumentStatus> {
-        let doc_id = doc.document_id.clone();
-        let url = format!("{}/document/{}", self.url, doc_id);
+        let url = self.endpoint(&["document", &doc.document_id]);
 
         let key = doc.document_key.clone();
         let params = vec![("document_key", key)];
 
-        let resp = self
-            .post(url)
-            .form(&params)
-            .send()
-            .map_err(Error::Reqwest)?;
+        let resp = self.send(self.post(url).form(&params))?;
 
         if !resp.status().is_success() {
             return super::convert(resp);
         }
 
-        resp.json().map_err(|_| Error::Deserialize)
+        resp.json().map_err(Error::deserialize)
     }
 
     /// POST /document/`{document_id}`/result
@@ -175,30 +395,231 @@ impl DeepL {
     /// Download translated document.
     ///
     /// If no `out_file` is given, the returned file path will have the name of the
-    /// [`Document`] id.
-    pub fn document_download(&self, doc: Document, out_file: Option<PathBuf>) -> Result<PathBuf> {
-        let doc_id = doc.document_id;
-        let url = format!("{}/document/{}/result", self.url, doc_id);
+    /// [`Document`] id. The document is written to a temporary file in the same
+    /// directory, which is renamed to the output path once the download completes, so
+    /// an interrupted download doesn't leave a truncated file behind.
+    ///
+    /// DeepL deletes the translation after it has been downloaded once, so if writing the
+    /// file fails the result is lost. Use
+    /// [`document_download_bytes`](Self::document_download_bytes) to keep the result in
+    /// memory and handle storing it yourself.
+    pub fn document_download(&self, doc: &Document, out_file: Option<PathBuf>) -> Result<PathBuf> {
+        let mut resp = self.document_result(doc)?;
 
-        let params = vec![("document_key", doc.document_key)];
+        let path = out_file.unwrap_or(PathBuf::from(&doc.document_id));
+        let mut part = path.clone().into_os_string();
+        part.push(".part");
+        let part = PathBuf::from(part);
 
-        let mut resp = self
-            .post(url)
-            .form(&params)
-            .send()
-            .map_err(Error::Reqwest)?;
+        let res = fs::File::create(&part).and_then(|mut file| {
+            io::copy(&mut resp, &mut file)?;
+            file.sync_all()?;
+            fs::rename(&part, &path)
+        });
+        if let Err(e) = res {
+            let _ = fs::remove_file(&part);
+            return Err(e.into());
+        }
+
+        Ok(path)
+    }
+
+    /// POST /document/`{document_id}`/result
+    ///
+    /// Download translated document, streaming it to `writer` without buffering the whole
+    /// document in memory. Returns the number of bytes written.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::*;
+    /// # let dl = DeepL::new(&std::env::var("DEEPL_API_KEY").unwrap());
+    /// # let doc = Document {
+    /// #     document_id: String::new(),
+    /// #     document_key: String::new(),
+    /// # };
+    /// let file = std::fs::File::create("translated.pdf").unwrap();
+    /// let len = dl.document_download_to(&doc, file).unwrap();
+    /// println!("Downloaded {len} bytes");
+    /// ```
+    pub fn document_download_to<W>(&self, doc: &Document, mut writer: W) -> Result<u64>
+    where
+        W: io::Write,
+    {
+        let mut resp = self.document_result(doc)?;
+        Ok(io::copy(&mut resp, &mut writer)?)
+    }
+
+    /// POST /document/`{document_id}`/result
+    ///
+    /// Download translated document into memory, without touching the filesystem.
+    pub fn document_download_bytes(&self, doc: &Document) -> Result<Vec<u8>> {
+        let mut buf = vec![];
+        self.document_download_to(doc, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Requests the translated document, returning the successful response
+    fn document_result(&self, doc: &Document) -> Result<reqwest::blocking::Response> {
+        let url = self.endpoint(&["document", &doc.document_id, "result"]);
+
+        let params = vec![("document_key", doc.document_key.clone())];
+
+        let resp = self.send(self.post(url).form(&params))?;
 
         if !resp.status().is_success() {
             return super::convert(resp);
         }
 
-        // write out file
-        let mut buf: Vec<u8> = vec![];
-        let _ = resp.copy_to(&mut buf).map_err(Error::Reqwest)?;
+        Ok(resp)
+    }
+
+    /// Translate a document, performing the upload, status polling, and download in one call.
+    ///
+    /// The translation status is checked every `poll_interval` until the document is done.
+    /// If a `timeout` is given, we stop waiting once it has elapsed. See
+    /// [`document_download`](Self::document_download) for a description of `out_file`.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::*;
+    /// # use std::{path::PathBuf, time::Duration};
+    /// # let dl = DeepL::new(&std::env::var("DEEPL_API_KEY").unwrap());
+    /// let opt = DocumentOptions::new(Language::DE, PathBuf::from("test.txt"));
+    /// let path = dl.translate_document(
+    ///     opt,
+    ///     Some(PathBuf::from("test-translated.txt")),
+    ///     Duration::from_secs(1),
+    ///     Some(Duration::from_secs(60)),
+    /// )
+    /// .unwrap();
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`Error::Document`] with the server's error message if translation fails,
+    /// or [`Error::Client`] if the timeout elapses before the document is done.
+    pub fn translate_document(
+        &self,
+        opt: DocumentOptions,
+        out_file: Option<PathBuf>,
+        poll_interval: Duration,
+        timeout: Option<Duration>,
+    ) -> Result<PathBuf> {
+        let doc = self.document_upload(opt)?;
+        self.document_wait(&doc, poll_interval, timeout, |_| {})?;
+        self.document_download(&doc, out_file)
+    }
+
+    /// Resume an interrupted document translation from a persisted `document_id` and
+    /// `document_key`, polling the status until done and downloading the result. See
+    /// [`document_download`](Self::document_download) for a description of `out_file`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`Error::Document`] if the document no longer exists on the server,
+    /// e.g. because the handle expired or the result was already downloaded.
+    pub fn document_resume(
+        &self,
+        document_id: String,
+        document_key: String,
+        out_file: Option<PathBuf>,
+    ) -> Result<PathBuf> {
+        let doc = Document {
+            document_id,
+            document_key,
+        };
+
+        match self.document_wait(&doc, self.config.poll_interval, None, |_| {}) {
+            Err(Error::Server { status, .. }) if status == StatusCode::NOT_FOUND => {
+                return Err(Error::Document(format!(
+                    "document {} not found, the handle may have expired",
+                    doc.document_id
+                )));
+            }
+            res => res?,
+        };
 
-        let path = out_file.unwrap_or(PathBuf::from(doc_id));
-        std::fs::write(&path, buf).map_err(Error::Io)?;
+        self.document_download(&doc, out_file)
+    }
 
-        Ok(path)
+    /// Polls the status of a document every `interval` until it is done translating,
+    /// calling `on_update` with each status received, e.g. to report
+    /// [`seconds_remaining`](DocumentStatus::seconds_remaining) as progress.
+    /// Returns the final status, which includes the
+    /// [`billed_characters`](DocumentStatus::billed_characters).
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`Error::Document`] if the translation fails on the server. The failed
+    /// status is passed to `on_update` before returning.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::*;
+    /// # use std::{env, path::PathBuf, time::Duration};
+    /// # let dl = DeepL::new(&env::var("DEEPL_API_KEY").unwrap());
+    /// let opt = DocumentOptions::new(Language::DE, PathBuf::from("test.txt"));
+    /// let doc = dl.document_upload(opt).unwrap();
+    ///
+    /// let status = dl
+    ///     .document_status_poll(&doc, Duration::from_secs(1), |status| {
+    ///         if let Some(secs) = status.seconds_remaining {
+    ///             println!("{secs} seconds remaining");
+    ///         }
+    ///     })
+    ///     .unwrap();
+    /// println!("Billed characters: {:?}", status.billed_characters);
+    /// ```
+    pub fn document_status_poll<F>(
+        &self,
+        doc: &Document,
+        interval: Duration,
+        on_update: F,
+    ) -> Result<DocumentStatus>
+    where
+        F: FnMut(&DocumentStatus),
+    {
+        self.document_wait(doc, interval, None, on_update)
+    }
+
+    /// Polls the status of a document until it is done translating
+    fn document_wait<F>(
+        &self,
+        doc: &Document,
+        interval: Duration,
+        timeout: Option<Duration>,
+        mut on_update: F,
+    ) -> Result<DocumentStatus>
+    where
+        F: FnMut(&DocumentStatus),
+    {
+        let start = Instant::now();
+
+        loop {
+            let status = self.document_status(doc)?;
+            on_update(&status);
+            match status.status {
+                DocState::Done => return Ok(status),
+                DocState::Error => {
+                    let msg = status
+                        .error_message
+                        .unwrap_or_else(|| "unknown error".to_string());
+                    return Err(Error::Document(msg));
+                }
+                DocState::Queued | DocState::Translating => {}
+            }
+
+            if let Some(timeout) = timeout {
+                if start.elapsed() + interval > timeout {
+                    return Err(Error::Client(
+                        "timed out waiting for document translation".to_string(),
+                    ));
+                }
+            }
+            thread::sleep(interval);
+        }
     }
 }
@@ -1,11 +1,16 @@
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fmt};
+use std::io::{BufRead, BufReader};
+use std::{collections::HashMap, fmt, str::FromStr};
 
 use reqwest::header;
+use reqwest::StatusCode;
 
 use super::{Error, Result};
 use crate::{DeepL, Language};
 
+/// Parsed glossary entries and the entry count they were fetched at, keyed by glossary id
+pub(crate) type EntriesCache = HashMap<String, (u64, HashMap<String, String>)>;
+
 /// A glossary language pair
 #[derive(Debug, Deserialize, Serialize)]
 pub struct GlossaryLanguagePair {
@@ -29,10 +34,28 @@ pub enum GlossaryEntriesFormat {
     Tsv,
     /// Comma-separated values
     Csv,
+    /// TermBase eXchange (TBX) XML. DeepL doesn't accept TBX directly, so entries in this
+    /// format are converted to TSV on the client, keeping terms in the glossary's languages.
+    Tbx,
+}
+
+/// The parameters of a glossary to create, see [`DeepL::glossaries_new_bulk`]
+#[derive(Clone, Debug)]
+pub struct GlossarySpec {
+    /// Glossary name
+    pub name: String,
+    /// Source language
+    pub source_lang: Language,
+    /// Target language
+    pub target_lang: Language,
+    /// Glossary entries
+    pub entries: String,
+    /// Format of the entries
+    pub fmt: GlossaryEntriesFormat,
 }
 
 /// Information that uniquely identifies a glossary
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub struct Glossary {
     /// A unique ID assigned to a glossary
     pub glossary_id: String,
@@ -64,30 +87,298 @@ impl AsRef<str> for GlossaryEntriesFormat {
         match self {
             Self::Tsv => "tsv",
             Self::Csv => "csv",
+            Self::Tbx => "tbx",
         }
     }
 }
 
+/// A glossary that is deleted when dropped, see [`DeepL::glossary_new_scoped`]
+///
+/// Derefs to the [`Glossary`] it holds. Since `Drop` can't return an error, a failure to
+/// delete the glossary is ignored.
+pub struct ScopedGlossary<'a> {
+    dl: &'a DeepL,
+    glossary: Option<Glossary>,
+}
+
+impl ScopedGlossary<'_> {
+    /// Takes the glossary out of the guard, so that it is not deleted
+    pub fn into_inner(mut self) -> Glossary {
+        self.glossary.take().expect("glossary is only taken once")
+    }
+}
+
+impl std::ops::Deref for ScopedGlossary<'_> {
+    type Target = Glossary;
+
+    fn deref(&self) -> &Glossary {
+        self.glossary.as_ref().expect("glossary is only taken once")
+    }
+}
+
+impl Drop for ScopedGlossary<'_> {
+    fn drop(&mut self) {
+        if let Some(glossary) = &self.glossary {
+            let _ = self.dl.glossary_delete(&glossary.glossary_id);
+        }
+    }
+}
+
+impl Glossary {
+    /// Parses the glossary's source and target languages.
+    ///
+    /// ## Errors
+    ///
+    /// If either language code is not a known [`Language`]
+    pub fn language_pair(&self) -> Result<(Language, Language)> {
+        let src = Language::from_str(&self.source_lang)?;
+        let trg = Language::from_str(&self.target_lang)?;
+        Ok((src, trg))
+    }
+}
+
 impl fmt::Display for GlossaryEntriesFormat {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.as_ref())
     }
 }
 
+impl GlossaryEntriesFormat {
+    /// Guesses the format of glossary entries from their first non-blank line.
+    ///
+    /// A line containing a tab is taken to be TSV, and one containing a comma but no tab
+    /// is taken to be CSV. Otherwise the format is ambiguous and `None` is returned.
+    /// This is a heuristic: it only looks at one line and never detects TBX.
+    ///
+    /// ```rust
+    /// # use deeprl::GlossaryEntriesFormat;
+    /// let fmt = GlossaryEntriesFormat::detect("hello\tciao\n");
+    /// assert!(matches!(fmt, Some(GlossaryEntriesFormat::Tsv)));
+    ///
+    /// let fmt = GlossaryEntriesFormat::detect("hello,ciao\n");
+    /// assert!(matches!(fmt, Some(GlossaryEntriesFormat::Csv)));
+    /// ```
+    pub fn detect(entries: &str) -> Option<GlossaryEntriesFormat> {
+        let line = entries.lines().find(|line| !line.trim().is_empty())?;
+
+        if line.contains('\t') {
+            Some(Self::Tsv)
+        } else if line.contains(',') {
+            Some(Self::Csv)
+        } else {
+            None
+        }
+    }
+}
+
+/// Parses TSV glossary entries into a map of source to target words.
+///
+/// The text contains newline-separated entries where each entry contains two strings
+/// separated by a tab. If `strict`, a non-empty line that doesn't split into exactly
+/// two words is an error, otherwise it is skipped.
+fn parse_entries(text: &str, strict: bool) -> Result<HashMap<String, String>> {
+    let mut map = HashMap::new();
+
+    for (i, entry) in text.split('\n').enumerate() {
+        let words: Vec<&str> = entry.split('\t').collect();
+        if words.len() != 2 {
+            if strict && !entry.is_empty() {
+                return Err(Error::GlossaryEntry(i + 1, entry.to_string()));
+            }
+            continue;
+        }
+        map.insert(words[0].to_string(), words[1].to_string());
+    }
+
+    Ok(map)
+}
+
+/// Checks glossary entries in TSV or CSV format the way DeepL would, returning
+/// [`Error::GlossaryEntry`] for the first line that has the wrong number of fields,
+/// an empty source or target, or a source already defined on an earlier line.
+/// Blank lines are skipped.
+pub(crate) fn validate_entries(text: &str, fmt: GlossaryEntriesFormat) -> Result<()> {
+    let mut sources = HashMap::new();
+
+    for (i, line) in text.split('\n').enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.trim().is_empty() {
+            continue;
+        }
+        let invalid = || Error::GlossaryEntry(i + 1, line.to_string());
+
+        let fields = match fmt {
+            GlossaryEntriesFormat::Csv => csv_fields(line),
+            _ => line.split('\t').map(str::to_string).collect(),
+        };
+        // csv entries may optionally repeat the source and target languages
+        let count_ok = match fmt {
+            GlossaryEntriesFormat::Csv => matches!(fields.len(), 2 | 4),
+            _ => fields.len() == 2,
+        };
+        if !count_ok {
+            return Err(invalid());
+        }
+
+        let (source, target) = (fields[0].trim(), fields[1].trim());
+        if source.is_empty() || target.is_empty() {
+            return Err(invalid());
+        }
+        if sources.insert(source.to_string(), i).is_some() {
+            return Err(invalid());
+        }
+    }
+
+    Ok(())
+}
+
+/// Splits a line of CSV into fields, honoring double quoted fields
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+
+    fields
+}
+
+/// Converts TBX entries to TSV, keeping the terms given in `source_lang` and `target_lang`.
+///
+/// Both TBX 2 (`termEntry`/`langSet`) and TBX 3 (`conceptEntry`/`langSec`) layouts are
+/// supported. Entries lacking a term in either language are skipped.
+pub(crate) fn tbx_to_tsv(
+    tbx: &str,
+    source_lang: Language,
+    target_lang: Language,
+) -> Result<String> {
+    let mut tsv = String::new();
+
+    for entry in ["termEntry", "conceptEntry"]
+        .into_iter()
+        .flat_map(|name| xml_elements(tbx, name))
+        .map(|(_, body)| body)
+    {
+        let mut source = None;
+        let mut target = None;
+
+        for (tag, body) in ["langSet", "langSec"]
+            .into_iter()
+            .flat_map(|name| xml_elements(entry, name))
+        {
+            let Some(lang) = xml_attribute(tag, "xml:lang") else {
+                continue;
+            };
+            let term = xml_elements(body, "term")
+                .first()
+                .map(|(_, term)| xml_unescape(term.trim()));
+
+            if lang_matches(lang, source_lang) {
+                source = source.or(term);
+            } else if lang_matches(lang, target_lang) {
+                target = target.or(term);
+            }
+        }
+
+        if let (Some(source), Some(target)) = (source, target) {
+            tsv.push_str(&format!("{source}\t{target}\n"));
+        }
+    }
+
+    if tsv.is_empty() {
+        return Err(Error::Client(format!(
+            "no TBX entries found for {source_lang}->{target_lang}"
+        )));
+    }
+
+    Ok(tsv)
+}
+
+/// Returns the opening tag and content of each element called `name`
+fn xml_elements<'a>(xml: &'a str, name: &str) -> Vec<(&'a str, &'a str)> {
+    let open = format!("<{name}");
+    let close = format!("</{name}>");
+    let mut found = vec![];
+    let mut rest = xml;
+
+    while let Some(start) = rest.find(&open) {
+        let after = &rest[start + open.len()..];
+        // match the whole name, e.g. `<term` but not `<termEntry`
+        if !after.starts_with(|c: char| c == '>' || c == '/' || c.is_whitespace()) {
+            rest = after;
+            continue;
+        }
+        let Some(tag_end) = after.find('>') else {
+            break;
+        };
+        let tag = &after[..tag_end];
+        let body = &after[tag_end + 1..];
+        if tag.ends_with('/') {
+            rest = body;
+            continue;
+        }
+        let Some(end) = body.find(&close) else {
+            break;
+        };
+        found.push((tag, &body[..end]));
+        rest = &body[end + close.len()..];
+    }
+
+    found
+}
+
+/// Returns the value of attribute `name` in an opening tag
+fn xml_attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let start = tag.find(&format!("{name}="))? + name.len() + 1;
+    let rest = &tag[start..];
+    let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let rest = &rest[1..];
+    let end = rest.find(quote)?;
+
+    Some(&rest[..end])
+}
+
+/// Replaces the predefined XML entities
+pub(crate) fn xml_unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Whether a language tag such as `en-US` refers to `lang`
+fn lang_matches(tag: &str, lang: Language) -> bool {
+    let primary = tag.split(['-', '_']).next().unwrap_or_default();
+    primary.eq_ignore_ascii_case(lang.base().as_ref())
+}
+
 impl DeepL {
     /// GET /glossary-language-pairs
     ///
     /// Get supported glossary language pairs
     pub fn glossary_languages(&self) -> Result<GlossaryLanguagePairsResult> {
-        let url = format!("{}/glossary-language-pairs", self.url);
+        let url = self.endpoint(&["glossary-language-pairs"]);
 
-        let resp = self.get(url).send().map_err(Error::Reqwest)?;
+        let resp = self.send(self.get(url))?;
 
         if !resp.status().is_success() {
             return super::convert(resp);
         }
 
-        resp.json().map_err(|_| Error::Deserialize)
+        resp.json().map_err(Error::deserialize)
     }
 
     /// POST /glossaries
@@ -100,6 +391,12 @@ impl DeepL {
     /// translation might otherwise be unreliable or ambiguous. A given glossary is defined by one
     /// source language and one target language where the source word in each entry is unique.
     ///
+    /// ## Errors
+    ///
+    /// TSV and CSV entries are checked before sending. If a line has the wrong number of
+    /// fields, an empty source or target, or repeats an earlier source, an
+    /// [`Error::GlossaryEntry`] is returned with the line number and content.
+    ///
     /// ## Example
     ///
     /// ```rust,no_run
@@ -121,6 +418,8 @@ impl DeepL {
     /// .unwrap();
     /// assert!(!glossary.glossary_id.is_empty());
     /// ```
+    ///
+    /// Entries in [`GlossaryEntriesFormat::Tbx`] are converted to TSV before sending.
     pub fn glossary_new(
         &self,
         name: String,
@@ -129,7 +428,16 @@ impl DeepL {
         entries: String,
         fmt: GlossaryEntriesFormat,
     ) -> Result<Glossary> {
-        let url = format!("{}/glossaries", self.url);
+        let url = self.endpoint(&["glossaries"]);
+
+        let (entries, fmt) = match fmt {
+            GlossaryEntriesFormat::Tbx => (
+                tbx_to_tsv(&entries, source_lang, target_lang)?,
+                GlossaryEntriesFormat::Tsv,
+            ),
+            _ => (entries, fmt),
+        };
+        validate_entries(&entries, fmt)?;
 
         let params = HashMap::from([
             ("name", name),
@@ -139,93 +447,444 @@ impl DeepL {
             ("entries_format", fmt.to_string()),
         ]);
 
-        let resp = self
-            .post(url)
-            .form(&params)
-            .send()
-            .map_err(Error::Reqwest)?;
+        let resp = self.send(self.post(url).form(&params))?;
 
         if !resp.status().is_success() {
             return super::convert(resp);
         }
 
-        resp.json().map_err(|_| Error::Deserialize)
+        resp.json().map_err(Error::deserialize)
+    }
+
+    /// POST /glossaries
+    ///
+    /// Like [`glossary_new`](Self::glossary_new), but returns a [`ScopedGlossary`] which
+    /// deletes the glossary when dropped, e.g. at the end of a test. Call
+    /// [`into_inner`](ScopedGlossary::into_inner) to keep the glossary.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::*;
+    /// # let dl = DeepL::new(&std::env::var("DEEPL_API_KEY").unwrap());
+    /// let glossary = dl
+    ///     .glossary_new_scoped(
+    ///         "my_glossary".to_string(),
+    ///         Language::EN,
+    ///         Language::IT,
+    ///         "hello\tciao".to_string(),
+    ///         GlossaryEntriesFormat::Tsv,
+    ///     )
+    ///     .unwrap();
+    ///
+    /// let opt = TextOptions::new(Language::IT)
+    ///     .source_lang(Language::EN)
+    ///     .glossary_id(glossary.glossary_id.clone());
+    /// let result = dl.translate(opt, vec!["hello".to_string()]).unwrap();
+    ///
+    /// // the glossary is deleted here
+    /// drop(glossary);
+    /// ```
+    pub fn glossary_new_scoped(
+        &self,
+        name: String,
+        source_lang: Language,
+        target_lang: Language,
+        entries: String,
+        fmt: GlossaryEntriesFormat,
+    ) -> Result<ScopedGlossary<'_>> {
+        let glossary = self.glossary_new(name, source_lang, target_lang, entries, fmt)?;
+
+        Ok(ScopedGlossary {
+            dl: self,
+            glossary: Some(glossary),
+        })
+    }
+
+    /// POST /glossaries
+    ///
+    /// Create several glossaries, one after another, e.g. one per target language of a project.
+    ///
+    /// ## Errors
+    ///
+    /// If creating any of the glossaries fails, the ones already created are deleted and the
+    /// error is returned. Rollback is best-effort: a glossary that fails to be deleted is
+    /// left in place.
+    pub fn glossaries_new_bulk(&self, specs: Vec<GlossarySpec>) -> Result<Vec<Glossary>> {
+        let mut created = Vec::with_capacity(specs.len());
+
+        for spec in specs {
+            let res = self.glossary_new(
+                spec.name,
+                spec.source_lang,
+                spec.target_lang,
+                spec.entries,
+                spec.fmt,
+            );
+            match res {
+                Ok(glossary) => created.push(glossary),
+                Err(e) => {
+                    for glossary in &created {
+                        let _ = self.glossary_delete(&glossary.glossary_id);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(created)
+    }
+
+    /// POST /glossaries
+    ///
+    /// Like [`glossary_new`](Self::glossary_new), but first checks that the language pair is
+    /// supported for glossaries, see [`glossary_languages`](Self::glossary_languages). This
+    /// costs an extra request, but gives a clearer error than the one returned by the server.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an [`Error::Client`] naming the pair, e.g. "unsupported glossary language
+    /// pair EN->JA", if the pair is not supported.
+    pub fn glossary_new_checked(
+        &self,
+        name: String,
+        source_lang: Language,
+        target_lang: Language,
+        entries: String,
+        fmt: GlossaryEntriesFormat,
+    ) -> Result<Glossary> {
+        // glossary pairs are listed by lowercase base language, e.g. "en"
+        let src = source_lang.base().to_string();
+        let trg = target_lang.base().to_string();
+
+        let supported = self
+            .glossary_languages()?
+            .supported_languages
+            .iter()
+            .any(|pair| {
+                pair.source_lang.eq_ignore_ascii_case(&src)
+                    && pair.target_lang.eq_ignore_ascii_case(&trg)
+            });
+        if !supported {
+            return Err(Error::Client(format!(
+                "unsupported glossary language pair {src}->{trg}"
+            )));
+        }
+
+        self.glossary_new(name, source_lang, target_lang, entries, fmt)
+    }
+
+    /// POST /glossaries
+    ///
+    /// Like [`glossary_new`](Self::glossary_new), but the format of the entries is detected
+    /// with [`GlossaryEntriesFormat::detect`]. Detection is a heuristic, so prefer
+    /// `glossary_new` when the format is known.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an [`Error::Client`] if the format can't be detected.
+    pub fn glossary_new_auto(
+        &self,
+        name: String,
+        source_lang: Language,
+        target_lang: Language,
+        entries: String,
+    ) -> Result<Glossary> {
+        let fmt = GlossaryEntriesFormat::detect(&entries)
+            .ok_or_else(|| Error::Client("could not detect glossary entries format".to_string()))?;
+
+        self.glossary_new(name, source_lang, target_lang, entries, fmt)
     }
 
     /// GET /glossaries
     ///
     /// List current active glossaries
     pub fn glossaries(&self) -> Result<GlossariesResult> {
-        let url = format!("{}/glossaries", self.url);
+        let url = self.endpoint(&["glossaries"]);
 
-        let resp = self.get(url).send().map_err(Error::Reqwest)?;
+        let resp = self.send(self.get(url))?;
 
         if !resp.status().is_success() {
             return super::convert(resp);
         }
 
-        resp.json().map_err(|_| Error::Deserialize)
+        resp.json().map_err(Error::deserialize)
+    }
+
+    /// List glossaries that can be used to translate from `src` to `trg`, see
+    /// [`glossaries_for_pair`](Self::glossaries_for_pair).
+    #[deprecated(note = "use glossaries_for_pair")]
+    pub fn glossaries_for(&self, src: Language, trg: Language) -> Result<Vec<Glossary>> {
+        self.glossaries_for_pair(src, trg)
+    }
+
+    /// List glossaries that can be used to translate from `source` to `target`.
+    ///
+    /// Glossaries are defined for base languages, so a regional variant such as
+    /// [`Language::ENGB`] matches glossaries for `en`. Language codes are compared
+    /// ignoring case, since DeepL reports them in lowercase.
+    pub fn glossaries_for_pair(&self, source: Language, target: Language) -> Result<Vec<Glossary>> {
+        let (source, target) = (source.base(), target.base());
+
+        self.find_glossaries(|glos| {
+            glos.source_lang.eq_ignore_ascii_case(source.as_ref())
+                && glos.target_lang.eq_ignore_ascii_case(target.as_ref())
+        })
+    }
+
+    /// List glossaries whose name is exactly `name`. DeepL allows several glossaries
+    /// to share a name, so more than one may be returned.
+    pub fn find_glossaries_by_name(&self, name: &str) -> Result<Vec<Glossary>> {
+        self.find_glossaries(|glos| glos.name == name)
+    }
+
+    /// Like [`find_glossaries_by_name`](Self::find_glossaries_by_name), but ignores
+    /// case when comparing names
+    pub fn find_glossaries_by_name_ignore_case(&self, name: &str) -> Result<Vec<Glossary>> {
+        let name = name.to_lowercase();
+        self.find_glossaries(|glos| glos.name.to_lowercase() == name)
+    }
+
+    /// Lists glossaries, keeping those matching the predicate
+    fn find_glossaries<P>(&self, predicate: P) -> Result<Vec<Glossary>>
+    where
+        P: Fn(&Glossary) -> bool,
+    {
+        let glossaries = self
+            .glossaries()?
+            .glossaries
+            .into_iter()
+            .filter(predicate)
+            .collect();
+
+        Ok(glossaries)
+    }
+
+    /// Whether a glossary with the given id exists.
+    ///
+    /// ## Errors
+    ///
+    /// Any error other than the glossary not being found, e.g. an invalid API key.
+    pub fn glossary_exists(&self, glossary_id: &str) -> Result<bool> {
+        match self.glossary_info(glossary_id) {
+            Ok(_) => Ok(true),
+            Err(Error::Server { status, .. }) if status == StatusCode::NOT_FOUND => Ok(false),
+            Err(e) => Err(e),
+        }
     }
 
     /// GET /glossaries/`{glossary_id}`
     ///
     /// Get meta information for a specified glossary (excluding entries)
     pub fn glossary_info(&self, glossary_id: &str) -> Result<Glossary> {
-        let url = format!("{}/glossaries/{}", self.url, glossary_id);
+        let url = self.endpoint(&["glossaries", glossary_id]);
 
-        let resp = self.get(url).send().map_err(Error::Reqwest)?;
+        let resp = self.send(self.get(url))?;
 
         if !resp.status().is_success() {
             return super::convert(resp);
         }
 
-        resp.json().map_err(|_| Error::Deserialize)
+        resp.json().map_err(Error::deserialize)
+    }
+
+    /// GET /glossaries/`{glossary_id}`/entries
+    ///
+    /// Retrieve entries for a specified glossary. Lines which can't be parsed as an entry
+    /// are skipped, see [`glossary_entries_checked`](Self::glossary_entries_checked) for a
+    /// strict alternative.
+    pub fn glossary_entries(&self, glossary_id: &str) -> Result<HashMap<String, String>> {
+        let t = self.glossary_entries_tsv(glossary_id)?;
+        parse_entries(&t, false)
     }
 
     /// GET /glossaries/`{glossary_id}`/entries
     ///
     /// Retrieve entries for a specified glossary.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`Error::GlossaryEntry`] with the line number of the first non-empty line
+    /// that can't be parsed as an entry.
+    pub fn glossary_entries_checked(&self, glossary_id: &str) -> Result<HashMap<String, String>> {
+        let t = self.glossary_entries_tsv(glossary_id)?;
+        parse_entries(&t, true)
+    }
+
+    /// Look up the target term for `source_term` in the specified glossary.
+    ///
+    /// The parsed entries are cached on the client keyed by glossary id and entry count,
+    /// so repeated lookups only fetch the glossary's info rather than all of its entries.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::DeepL;
+    /// # let dl = DeepL::new(&std::env::var("DEEPL_API_KEY").unwrap());
+    /// # let glossary_id = "def3a26b-3e84-45b3-84ae-0c0aaf3525f7";
+    /// if let Some(target) = dl.glossary_lookup(glossary_id, "hello").unwrap() {
+    ///     println!("hello -> {target}");
+    /// }
+    /// ```
+    pub fn glossary_lookup(&self, glossary_id: &str, source_term: &str) -> Result<Option<String>> {
+        let entry_count = self.glossary_info(glossary_id)?.entry_count;
+
+        if let Some((count, entries)) = self.glossary_cache.lock().unwrap().get(glossary_id) {
+            if *count == entry_count {
+                return Ok(entries.get(source_term).cloned());
+            }
+        }
+
+        let entries = self.glossary_entries(glossary_id)?;
+        let target = entries.get(source_term).cloned();
+
+        self.glossary_cache
+            .lock()
+            .unwrap()
+            .insert(glossary_id.to_string(), (entry_count, entries));
+
+        Ok(target)
+    }
+
+    /// GET /glossaries/`{glossary_id}`/entries
+    ///
+    /// Retrieve entries for a specified glossary as an iterator, reading the response one
+    /// line at a time so that memory use doesn't grow with the size of the glossary.
+    ///
+    /// Empty lines are skipped. A line that can't be parsed as an entry yields an
+    /// [`Error::GlossaryEntry`] with its line number, and iteration may continue past it.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::*;
+    /// # let dl = DeepL::new(&std::env::var("DEEPL_API_KEY").unwrap());
+    /// # let glossary_id = "def3a26b-3e84-45b3-84ae-0c0aaf3525f7";
+    /// for entry in dl.glossary_entries_stream(glossary_id).unwrap() {
+    ///     let (source, target) = entry.unwrap();
+    ///     println!("{source} -> {target}");
+    /// }
+    /// ```
+    pub fn glossary_entries_stream(
+        &self,
+        glossary_id: &str,
+    ) -> Result<impl Iterator<Item = Result<(String, String)>>> {
+        let resp = self.glossary_entries_response(glossary_id)?;
+
+        let entries = BufReader::new(resp)
+            .lines()
+            .enumerate()
+            .filter_map(|(i, line)| {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(e) => return Some(Err(Error::Io(e))),
+                };
+                if line.is_empty() {
+                    return None;
+                }
+                match line.split_once('\t') {
+                    Some((source, target)) if !target.contains('\t') => {
+                        Some(Ok((source.to_string(), target.to_string())))
+                    }
+                    _ => Some(Err(Error::GlossaryEntry(i + 1, line))),
+                }
+            });
+
+        Ok(entries)
+    }
+
+    /// Fetches glossary entries as TSV
+    fn glossary_entries_tsv(&self, glossary_id: &str) -> Result<String> {
+        let resp = self.glossary_entries_response(glossary_id)?;
+        resp.text().map_err(|_| Error::InvalidResponse)
+    }
+
+    /// Requests glossary entries, returning the response with the entries as its body
     // Currently supports receiving entries in TSV format.
-    pub fn glossary_entries(&self, glossary_id: &str) -> Result<HashMap<String, String>> {
-        let url = format!("{}/glossaries/{}/entries", self.url, glossary_id);
+    fn glossary_entries_response(&self, glossary_id: &str) -> Result<reqwest::blocking::Response> {
+        let url = self.endpoint(&["glossaries", glossary_id, "entries"]);
         let accept = header::HeaderValue::from_static("text/tab-separated-values");
 
-        let resp = self
-            .get(url)
-            .header(header::ACCEPT, accept)
-            .send()
-            .map_err(Error::Reqwest)?;
+        let resp = self.send(self.get(url).header(header::ACCEPT, accept))?;
 
         if !resp.status().is_success() {
             return super::convert(resp);
         }
 
-        let t = resp.text().map_err(|_| Error::InvalidResponse).unwrap();
-        // The response text contains newline-separated entries
-        // where each entry contains two strings separated by a tab.
-        // First we split entries on '\n', then for each entry, split words
-        // on '\t' and build a map of source to target words
-        let raw_entries: Vec<&str> = t.split('\n').collect();
+        Ok(resp)
+    }
 
-        let mut map = HashMap::new();
-        for entry in raw_entries {
-            let words: Vec<&str> = entry.split('\t').collect();
-            if words.len() != 2 {
-                continue;
-            }
-            map.insert(words[0].to_string(), words[1].to_string());
+    /// Replace the entries of an existing glossary.
+    ///
+    /// DeepL doesn't support editing a glossary in place, so this fetches the glossary's
+    /// name and language pair, deletes it, and creates a new glossary from `entries`.
+    ///
+    /// Note: the returned [`Glossary`] has a new `glossary_id`, and the old id is no longer valid.
+    ///
+    /// ## Errors
+    ///
+    /// If `entries` is empty, an [`Error::Client`] is returned, and if an entry is invalid,
+    /// e.g. has an empty target or contains a tab or newline, an [`Error::GlossaryEntry`].
+    /// In both cases the glossary is left untouched.
+    ///
+    /// If the glossary was deleted but creating its replacement failed, an [`Error::Client`]
+    /// is returned saying so, in which case neither the old nor the new glossary exists.
+    pub fn glossary_replace_entries(
+        &self,
+        glossary_id: &str,
+        entries: HashMap<String, String>,
+    ) -> Result<Glossary> {
+        if entries.is_empty() {
+            return Err(Error::Client("glossary entries are empty".to_string()));
         }
 
-        Ok(map)
+        let mut entries: Vec<_> = entries.into_iter().collect();
+        entries.sort();
+        let entries: String = entries
+            .into_iter()
+            .map(|(source, target)| format!("{source}\t{target}\n"))
+            .collect();
+        // checked before anything is deleted
+        validate_entries(&entries, GlossaryEntriesFormat::Tsv)?;
+
+        let glossary = self.glossary_info(glossary_id)?;
+        let (source_lang, target_lang) = glossary.language_pair()?;
+
+        self.glossary_delete(glossary_id)?;
+
+        self.glossary_new(
+            glossary.name,
+            source_lang,
+            target_lang,
+            entries,
+            GlossaryEntriesFormat::Tsv,
+        )
+        .map_err(|e| {
+            Error::Client(format!(
+                "glossary {glossary_id} was deleted but its replacement could not be created: {e}"
+            ))
+        })
     }
 
     /// DELETE /glossaries/`{glossary_id}`
     ///
     /// Destroy a glossary
+    ///
+    /// ## Errors
+    ///
+    /// If the request fails or DeepL responds with an error, e.g. a 404 when no glossary
+    /// exists with the given id.
     pub fn glossary_delete(&self, glossary_id: &str) -> Result<()> {
-        let url = format!("{}/glossaries/{}", self.url, glossary_id);
+        let url = self.endpoint(&["glossaries", glossary_id]);
+
+        // entries cached for lookups are dropped with the glossary
+        self.glossary_cache.lock().unwrap().remove(glossary_id);
+
+        let resp = self.send(self.delete(url))?;
 
-        let _ = self.delete(url).send().map_err(Error::Reqwest);
+        if !resp.status().is_success() {
+            return super::convert(resp);
+        }
 
         Ok(())
     }
@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Error, Result};
+use crate::glos::{tbx_to_tsv, validate_entries};
+use crate::{DeepL, GlossaryEntriesFormat, Language};
+
+/// The entries for one language pair of a multilingual glossary
+#[derive(Clone, Debug)]
+pub struct GlossaryDictionary {
+    /// The language in which the source texts are specified
+    pub source_lang: Language,
+    /// The language in which the target texts are specified
+    pub target_lang: Language,
+    /// The entries of the dictionary
+    pub entries: String,
+    /// The format of the entries
+    pub entries_format: GlossaryEntriesFormat,
+}
+
+/// Meta information about one language pair of a multilingual glossary
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GlossaryDictionaryInfo {
+    /// The language in which the source texts are specified
+    pub source_lang: String,
+    /// The language in which the target texts are specified
+    pub target_lang: String,
+    /// The number of entries in the dictionary
+    pub entry_count: u64,
+}
+
+/// A multilingual glossary
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GlossaryV3 {
+    /// A unique ID assigned to a glossary
+    pub glossary_id: String,
+    /// Name associated with the glossary
+    pub name: String,
+    /// The language pairs contained in the glossary
+    pub dictionaries: Vec<GlossaryDictionaryInfo>,
+    /// The creation time of the glossary in ISO 8601-1:2019 format (e.g. 2021-08-03T14:16:18.329Z)
+    pub creation_time: String,
+}
+
+/// Request body for creating a multilingual glossary
+#[derive(Serialize)]
+struct GlossaryV3Params {
+    name: String,
+    dictionaries: Vec<DictionaryParams>,
+}
+
+/// A dictionary as sent to the server
+#[derive(Serialize)]
+struct DictionaryParams {
+    source_lang: String,
+    target_lang: String,
+    entries: String,
+    entries_format: String,
+}
+
+impl GlossaryDictionary {
+    /// Create a new dictionary for the given language pair
+    pub fn new(
+        source_lang: Language,
+        target_lang: Language,
+        entries: String,
+        entries_format: GlossaryEntriesFormat,
+    ) -> Self {
+        Self {
+            source_lang,
+            target_lang,
+            entries,
+            entries_format,
+        }
+    }
+
+    /// Converts the dictionary to request parameters, checking its entries
+    fn into_params(self) -> Result<DictionaryParams> {
+        let (entries, fmt) = match self.entries_format {
+            GlossaryEntriesFormat::Tbx => (
+                tbx_to_tsv(&self.entries, self.source_lang, self.target_lang)?,
+                GlossaryEntriesFormat::Tsv,
+            ),
+            fmt => (self.entries, fmt),
+        };
+        validate_entries(&entries, fmt)?;
+
+        Ok(DictionaryParams {
+            source_lang: self.source_lang.to_string(),
+            target_lang: self.target_lang.to_string(),
+            entries,
+            entries_format: fmt.to_string(),
+        })
+    }
+}
+
+impl DeepL {
+    /// POST /v3/glossaries
+    ///
+    /// Create a new multilingual glossary containing one dictionary per language pair.
+    ///
+    /// Unlike [`glossary_new`](Self::glossary_new), which uses the v2 API where a glossary
+    /// holds a single language pair, this uses the v3 glossaries API.
+    ///
+    /// ## Errors
+    ///
+    /// If no dictionaries are given, or if the entries of a dictionary are invalid,
+    /// see [`glossary_new`](Self::glossary_new).
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::*;
+    /// # let dl = DeepL::new(&std::env::var("DEEPL_API_KEY").unwrap());
+    /// let fmt = GlossaryEntriesFormat::Tsv;
+    /// let dictionaries = vec![
+    ///     GlossaryDictionary::new(Language::EN, Language::DE, "hello\tHallo".to_string(), fmt),
+    ///     GlossaryDictionary::new(Language::EN, Language::IT, "hello\tciao".to_string(), fmt),
+    /// ];
+    ///
+    /// let glossary = dl.glossary_v3_new("greetings".to_string(), dictionaries).unwrap();
+    /// assert_eq!(glossary.dictionaries.len(), 2);
+    /// ```
+    pub fn glossary_v3_new(
+        &self,
+        name: String,
+        dictionaries: Vec<GlossaryDictionary>,
+    ) -> Result<GlossaryV3> {
+        if dictionaries.is_empty() {
+            return Err(Error::Client("no glossary dictionaries given".to_string()));
+        }
+
+        let params = GlossaryV3Params {
+            name,
+            dictionaries: dictionaries
+                .into_iter()
+                .map(GlossaryDictionary::into_params)
+                .collect::<Result<_>>()?,
+        };
+
+        let resp = self.send(self.post(self.v3_url("glossaries")).json(&params))?;
+
+        if !resp.status().is_success() {
+            return super::convert(resp);
+        }
+
+        resp.json().map_err(Error::deserialize)
+    }
+
+    /// Url of a v3 endpoint, derived from the configured v2 base url
+    fn v3_url(&self, path: &str) -> reqwest::Url {
+        let mut url = self.url.clone();
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments
+                .pop_if_empty()
+                .pop()
+                .push("v3")
+                .extend(path.split('/'));
+        }
+        url
+    }
+}
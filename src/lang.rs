@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::str::FromStr;
 
@@ -6,7 +7,7 @@ use super::{Error, Result};
 use crate::DeepL;
 
 /// Language type. Note: this is currently only used when fetching language meta information.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum LanguageType {
     /// Source language
     Source,
@@ -15,7 +16,7 @@ pub enum LanguageType {
 }
 
 /// Information about a supported language
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct LanguageInfo {
     /// Language code (EN, DE, etc.)
     pub language: String,
@@ -41,87 +42,108 @@ impl Default for LanguageInfo {
     }
 }
 
-/// Language variants.
-///
-/// Please note that while many [`Language`] variants are interchangeable as both source and
-/// target languages, there are exceptions. For example when translating text and documents,
-/// the following may only be used as source languages:
-/// - `EN`
-/// - `PT`
-///
-/// and the following may only be used as target languages (representing regional variants):
-/// - `ENUS`
-/// - `ENGB`
-/// - `PTBR`
-/// - `PTPT`
-#[derive(Copy, Clone, Debug, PartialEq)]
-pub enum Language {
-    /// Bulgarian
-    BG,
-    /// Czech
-    CS,
-    /// Danish
-    DA,
-    /// German
-    DE,
-    /// Greek
-    EL,
-    /// English (source language)
-    EN,
-    /// English British (target language)
-    ENGB,
-    /// English American (target language)
-    ENUS,
-    /// Spanish
-    ES,
-    /// Estonian
-    ET,
-    /// Finish
-    FI,
-    /// French
-    FR,
-    /// Hungarian
-    HU,
-    /// Indonesian
-    ID,
-    /// Italian
-    IT,
-    /// Japanese
-    JA,
-    /// Korean
-    KO,
-    /// Lithuanian
-    LT,
-    /// Latvian
-    LV,
-    /// Norwegian
-    NB,
-    /// Dutch
-    NL,
-    /// Polish
-    PL,
-    /// Portuguese (source language)
-    PT,
-    /// Portuguese Brazilian (target language)
-    PTBR,
-    /// Portuguese European (target language)
-    PTPT,
-    /// Romanian
-    RO,
-    /// Russian
-    RU,
-    /// Slovak
-    SK,
-    /// Slovenian
-    SL,
-    /// Swedish
-    SV,
-    /// Turkish
-    TR,
-    /// Ukranian
-    UK,
-    /// Chinese simplified
-    ZH,
+/// Defines the [`Language`] enum along with `ALL`, a list of every variant, so that a
+/// new variant can't be left out of [`Language::all`]
+macro_rules! languages {
+    (
+        $(#[$meta:meta])*
+        pub enum Language {
+            $($(#[$doc:meta])* $variant:ident,)+
+        }
+    ) => {
+        $(#[$meta])*
+        pub enum Language {
+            $($(#[$doc])* $variant,)+
+        }
+
+        /// Every [`Language`] variant, in declaration order
+        const ALL: &[Language] = &[$(Language::$variant,)+];
+    };
+}
+
+languages! {
+    /// Language variants.
+    ///
+    /// Please note that while many [`Language`] variants are interchangeable as both source and
+    /// target languages, there are exceptions. For example when translating text and documents,
+    /// the following may only be used as source languages:
+    /// - `EN`
+    /// - `PT`
+    ///
+    /// and the following may only be used as target languages (representing regional variants):
+    /// - `ENUS`
+    /// - `ENGB`
+    /// - `PTBR`
+    /// - `PTPT`
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+    pub enum Language {
+        /// Bulgarian
+        BG,
+        /// Czech
+        CS,
+        /// Danish
+        DA,
+        /// German
+        DE,
+        /// Greek
+        EL,
+        /// English (source language)
+        EN,
+        /// English British (target language)
+        ENGB,
+        /// English American (target language)
+        ENUS,
+        /// Spanish
+        ES,
+        /// Estonian
+        ET,
+        /// Finish
+        FI,
+        /// French
+        FR,
+        /// Hungarian
+        HU,
+        /// Indonesian
+        ID,
+        /// Italian
+        IT,
+        /// Japanese
+        JA,
+        /// Korean
+        KO,
+        /// Lithuanian
+        LT,
+        /// Latvian
+        LV,
+        /// Norwegian
+        NB,
+        /// Dutch
+        NL,
+        /// Polish
+        PL,
+        /// Portuguese (source language)
+        PT,
+        /// Portuguese Brazilian (target language)
+        PTBR,
+        /// Portuguese European (target language)
+        PTPT,
+        /// Romanian
+        RO,
+        /// Russian
+        RU,
+        /// Slovak
+        SK,
+        /// Slovenian
+        SL,
+        /// Swedish
+        SV,
+        /// Turkish
+        TR,
+        /// Ukranian
+        UK,
+        /// Chinese simplified
+        ZH,
+    }
 }
 
 impl FromStr for Language {
@@ -212,17 +234,235 @@ impl AsRef<str> for Language {
     }
 }
 
+impl Language {
+    /// Every language, including regional variants, in alphabetical order of the code
+    pub fn all() -> &'static [Language] {
+        ALL
+    }
+
+    /// An iterator over every language, see [`Language::all`]
+    pub fn iter() -> impl Iterator<Item = Language> {
+        ALL.iter().copied()
+    }
+
+    /// Every language that may be used as a source language, i.e. excluding
+    /// target-only variants such as `EN-GB`
+    pub fn all_sources() -> Vec<Language> {
+        ALL.iter()
+            .copied()
+            .filter(|l| !l.is_target_only())
+            .collect()
+    }
+
+    /// Every language that may be used as a target language, i.e. excluding
+    /// source-only languages such as `EN`
+    pub fn all_targets() -> Vec<Language> {
+        ALL.iter()
+            .copied()
+            .filter(|l| !l.is_source_only())
+            .collect()
+    }
+
+    /// Whether the language may only be used as a source language, e.g. `EN`
+    pub fn is_source_only(&self) -> bool {
+        matches!(self, Self::EN | Self::PT)
+    }
+
+    /// Whether the language may only be used as a target language, e.g. `EN-GB`
+    pub fn is_target_only(&self) -> bool {
+        matches!(self, Self::ENGB | Self::ENUS | Self::PTBR | Self::PTPT)
+    }
+
+    /// Whether the language supports setting a formality as a target language, per the
+    /// languages DeepL reports with `supports_formality`, e.g. `DE` or `JA`
+    pub fn supports_formality(&self) -> bool {
+        matches!(
+            self,
+            Self::DE
+                | Self::ES
+                | Self::FR
+                | Self::IT
+                | Self::JA
+                | Self::NL
+                | Self::PL
+                | Self::PTBR
+                | Self::PTPT
+                | Self::RU
+        )
+    }
+
+    /// The English name of the language, e.g. `German` or `Portuguese (Brazilian)`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::BG => "Bulgarian",
+            Self::CS => "Czech",
+            Self::DA => "Danish",
+            Self::DE => "German",
+            Self::EL => "Greek",
+            Self::EN => "English",
+            Self::ENGB => "English (British)",
+            Self::ENUS => "English (American)",
+            Self::ES => "Spanish",
+            Self::ET => "Estonian",
+            Self::FI => "Finnish",
+            Self::FR => "French",
+            Self::HU => "Hungarian",
+            Self::ID => "Indonesian",
+            Self::IT => "Italian",
+            Self::JA => "Japanese",
+            Self::KO => "Korean",
+            Self::LT => "Lithuanian",
+            Self::LV => "Latvian",
+            Self::NB => "Norwegian (Bokmål)",
+            Self::NL => "Dutch",
+            Self::PL => "Polish",
+            Self::PT => "Portuguese",
+            Self::PTBR => "Portuguese (Brazilian)",
+            Self::PTPT => "Portuguese (European)",
+            Self::RO => "Romanian",
+            Self::RU => "Russian",
+            Self::SK => "Slovak",
+            Self::SL => "Slovenian",
+            Self::SV => "Swedish",
+            Self::TR => "Turkish",
+            Self::UK => "Ukrainian",
+            Self::ZH => "Chinese (simplified)",
+        }
+    }
+
+    /// The name of the language in the language itself, e.g. `Deutsch` or `日本語`
+    pub fn native_name(&self) -> &'static str {
+        match self {
+            Self::BG => "Български",
+            Self::CS => "Čeština",
+            Self::DA => "Dansk",
+            Self::DE => "Deutsch",
+            Self::EL => "Ελληνικά",
+            Self::EN => "English",
+            Self::ENGB => "English (UK)",
+            Self::ENUS => "English (US)",
+            Self::ES => "Español",
+            Self::ET => "Eesti",
+            Self::FI => "Suomi",
+            Self::FR => "Français",
+            Self::HU => "Magyar",
+            Self::ID => "Bahasa Indonesia",
+            Self::IT => "Italiano",
+            Self::JA => "日本語",
+            Self::KO => "한국어",
+            Self::LT => "Lietuvių",
+            Self::LV => "Latviešu",
+            Self::NB => "Norsk bokmål",
+            Self::NL => "Nederlands",
+            Self::PL => "Polski",
+            Self::PT => "Português",
+            Self::PTBR => "Português (Brasil)",
+            Self::PTPT => "Português (Portugal)",
+            Self::RO => "Română",
+            Self::RU => "Русский",
+            Self::SK => "Slovenčina",
+            Self::SL => "Slovenščina",
+            Self::SV => "Svenska",
+            Self::TR => "Türkçe",
+            Self::UK => "Українська",
+            Self::ZH => "简体中文",
+        }
+    }
+
+    /// Returns the ISO 639-1 code of the language, e.g. `en` for both `EN` and `EN-GB`
+    pub fn iso639_1(&self) -> &'static str {
+        match self {
+            Self::BG => "bg",
+            Self::CS => "cs",
+            Self::DA => "da",
+            Self::DE => "de",
+            Self::EL => "el",
+            Self::EN | Self::ENGB | Self::ENUS => "en",
+            Self::ES => "es",
+            Self::ET => "et",
+            Self::FI => "fi",
+            Self::FR => "fr",
+            Self::HU => "hu",
+            Self::ID => "id",
+            Self::IT => "it",
+            Self::JA => "ja",
+            Self::KO => "ko",
+            Self::LT => "lt",
+            Self::LV => "lv",
+            Self::NB => "nb",
+            Self::NL => "nl",
+            Self::PL => "pl",
+            Self::PT | Self::PTBR | Self::PTPT => "pt",
+            Self::RO => "ro",
+            Self::RU => "ru",
+            Self::SK => "sk",
+            Self::SL => "sl",
+            Self::SV => "sv",
+            Self::TR => "tr",
+            Self::UK => "uk",
+            Self::ZH => "zh",
+        }
+    }
+
+    /// Returns the BCP 47 language tag, e.g. `en-GB`
+    pub fn bcp47(&self) -> String {
+        match self {
+            Self::ENGB => "en-GB".to_string(),
+            Self::ENUS => "en-US".to_string(),
+            Self::PTBR => "pt-BR".to_string(),
+            Self::PTPT => "pt-PT".to_string(),
+            _ => self.iso639_1().to_string(),
+        }
+    }
+
+    /// Returns the base language of a regional variant, e.g. `EN` for `EN-GB`.
+    /// Languages without regional variants are returned as is.
+    pub fn base(&self) -> Language {
+        match self {
+            Self::ENGB | Self::ENUS => Self::EN,
+            Self::PTBR | Self::PTPT => Self::PT,
+            _ => *self,
+        }
+    }
+}
+
 impl fmt::Display for Language {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.as_ref())
     }
 }
 
+impl Serialize for Language {
+    /// Serializes the language as its code, e.g. `"PT-BR"`
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_ref())
+    }
+}
+
+impl<'de> Deserialize<'de> for Language {
+    /// Deserializes a language from its code, ignoring case, e.g. `"PT-BR"` or `"pt-br"`
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let code = String::deserialize(deserializer)?;
+        Language::from_str(&code)
+            .map_err(|_| serde::de::Error::custom(format!("unknown language code: {code}")))
+    }
+}
+
 impl DeepL {
     /// GET /languages
     ///
     /// Get information on supported languages.
     ///
+    /// The result is cached on the client per [`LanguageType`], so only the first call
+    /// hits the API. DeepL adds languages rarely, but a long-lived client will not see
+    /// them until [`DeepL::refresh_languages`] is called.
+    ///
     /// ## Example
     ///
     /// ```rust,no_run
@@ -236,7 +476,11 @@ impl DeepL {
     /// println!("{}", language.name); // Bulgarian
     ///```
     pub fn languages(&self, lang_type: LanguageType) -> Result<Vec<LanguageInfo>> {
-        let url = format!("{}/languages", self.url);
+        if let Some(langs) = self.languages_cache.read().unwrap().get(&lang_type) {
+            return Ok(langs.clone());
+        }
+
+        let url = self.endpoint(&["languages"]);
 
         let kind = match lang_type {
             LanguageType::Source => "source",
@@ -246,12 +490,102 @@ impl DeepL {
         // get, query "type"
         let q = vec![("type", kind)];
 
-        let resp = self.get(url).query(&q).send().map_err(Error::Reqwest)?;
+        let resp = self.send(self.get(url).query(&q))?;
 
         if !resp.status().is_success() {
             return super::convert(resp);
         }
 
-        resp.json().map_err(|_| Error::Deserialize)
+        let langs: Vec<LanguageInfo> = resp.json().map_err(Error::deserialize)?;
+
+        self.languages_cache
+            .write()
+            .unwrap()
+            .insert(lang_type, langs.clone());
+
+        Ok(langs)
+    }
+
+    /// Clear the cached language lists, so that the next call to [`DeepL::languages`]
+    /// fetches them from the API again.
+    pub fn refresh_languages(&self) {
+        self.languages_cache.write().unwrap().clear();
+    }
+
+    /// Get both source and target languages, merged into one list sorted by language code.
+    ///
+    /// Languages available as both source and target appear once, taken from the target
+    /// list so that `supports_formality` is populated.
+    pub fn languages_all(&self) -> Result<Vec<LanguageInfo>> {
+        let mut langs = BTreeMap::new();
+
+        for lang_type in [LanguageType::Source, LanguageType::Target] {
+            for info in self.languages(lang_type)? {
+                langs.insert(info.language.clone(), info);
+            }
+        }
+
+        Ok(langs.into_values().collect())
+    }
+
+    /// Whether `lang` supports the `formality` option when used as a target language.
+    ///
+    /// Backed by the cached list of target languages, see [`DeepL::languages`].
+    /// Source-only languages always return `false`.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::{DeepL, Language};
+    /// # let dl = DeepL::new(&std::env::var("DEEPL_API_KEY").unwrap());
+    /// assert!(dl.supports_formality(Language::DE).unwrap());
+    /// assert!(!dl.supports_formality(Language::EN).unwrap());
+    ///```
+    pub fn supports_formality(&self, lang: Language) -> Result<bool> {
+        if lang.is_source_only() {
+            return Ok(false);
+        }
+
+        let code = lang.to_string();
+        let supported = self
+            .languages(LanguageType::Target)?
+            .into_iter()
+            .find(|info| info.language.eq_ignore_ascii_case(&code))
+            .and_then(|info| info.supports_formality)
+            .unwrap_or(false);
+
+        Ok(supported)
+    }
+
+    /// GET /languages
+    ///
+    /// Get information on supported languages keyed by [`Language`]. Language codes
+    /// not modeled by this crate are skipped.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::{DeepL, Language, LanguageType};
+    /// # let dl = DeepL::new(&std::env::var("DEEPL_API_KEY").unwrap());
+    /// let target_langs = dl.languages_map(LanguageType::Target).unwrap();
+    ///
+    /// let german = &target_langs[&Language::DE];
+    /// assert_eq!(german.supports_formality, Some(true));
+    ///```
+    pub fn languages_map(
+        &self,
+        lang_type: LanguageType,
+    ) -> Result<HashMap<Language, LanguageInfo>> {
+        let langs = self.languages(lang_type)?;
+
+        let map = langs
+            .into_iter()
+            .filter_map(|info| {
+                let lang = Language::from_str(&info.language).ok()?;
+                Some((lang, info))
+            })
+            .collect();
+
+        Ok(map)
     }
 }
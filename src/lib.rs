@@ -54,7 +54,11 @@
 #![warn(missing_docs)]
 
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use reqwest::header;
 use reqwest::StatusCode;
@@ -62,28 +66,183 @@ use thiserror::Error;
 
 mod doc;
 mod glos;
+mod glos_v3;
 mod lang;
 mod text;
+mod write;
 
 pub use {
     doc::{DocState, Document, DocumentOptions, DocumentStatus},
     glos::{
         GlossariesResult, Glossary, GlossaryEntriesFormat, GlossaryLanguagePair,
-        GlossaryLanguagePairsResult,
+        GlossaryLanguagePairsResult, GlossarySpec, ScopedGlossary,
     },
+    glos_v3::{GlossaryDictionary, GlossaryDictionaryInfo, GlossaryV3},
     lang::{Language, LanguageInfo, LanguageType},
-    text::{Formality, SplitSentences, TagHandling, TextOptions, TranslateTextResult, Translation},
+    text::{
+        Formality, LenientTranslateResult, ModelType, SplitSentences, TagHandling, TextOptions,
+        TranslateTextResult, Translation,
+    },
+    write::{Improvement, RephraseResult, Tone, WriteOptions, WritingStyle},
 };
 
 // Sets the user agent request header value, e.g. 'deeprl/0.1.0'
 static APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"),);
 
+/// Delay before the first retry of a failed request, doubled on each further attempt
+const RETRY_BACKOFF: Duration = Duration::from_millis(250);
+
 /// The `DeepL` client struct
 pub struct DeepL {
     client: reqwest::blocking::Client,
     url: reqwest::Url,
-    user_agent: Option<String>,
+    free: bool,
+    config: ClientConfig,
     auth: String,
+    usage_cache: Mutex<Option<(Instant, Usage)>>,
+    languages_cache: RwLock<HashMap<LanguageType, Vec<LanguageInfo>>>,
+    glossary_cache: Mutex<glos::EntriesCache>,
+    max_characters: Option<u64>,
+    default_target: Option<Language>,
+    dry_run: bool,
+    document_slots: Option<doc::Semaphore>,
+    transport: Option<Box<dyn Transport>>,
+    on_response: Option<ResponseHook>,
+}
+
+/// Callback invoked with the endpoint path, status and elapsed time of each response,
+/// see [`DeepL::set_on_response`]
+pub type ResponseHook = Box<dyn Fn(&str, StatusCode, Duration) + Send + Sync>;
+
+/// Sends requests built by [`DeepL`], see [`DeepL::transport`]
+///
+/// Implemented for `reqwest::blocking::Client`, which sends requests over the network.
+/// A custom transport can, for example, return canned responses in tests.
+///
+/// ```rust
+/// # use deeprl::*;
+/// use reqwest::blocking::{Request, Response};
+///
+/// struct Canned(&'static str);
+///
+/// impl Transport for Canned {
+///     fn execute(&self, _req: Request) -> Result<Response, Error> {
+///         Ok(Response::from(http::Response::new(self.0)))
+///     }
+/// }
+///
+/// let mut dl = DeepL::new("key:fx");
+/// dl.transport(Canned(r#"{"character_count":180,"character_limit":500000}"#));
+/// assert_eq!(dl.usage().unwrap().character_count, 180);
+/// ```
+pub trait Transport: Send + Sync {
+    /// Executes the request, returning the response
+    fn execute(&self, req: reqwest::blocking::Request) -> Result<reqwest::blocking::Response>;
+}
+
+impl Transport for reqwest::blocking::Client {
+    fn execute(&self, req: reqwest::blocking::Request) -> Result<reqwest::blocking::Response> {
+        Ok(reqwest::blocking::Client::execute(self, req)?)
+    }
+}
+
+/// Client configuration, see [`DeepL::with_config`]
+///
+/// ```rust,no_run
+/// # use deeprl::*;
+/// # use std::time::Duration;
+/// let config = ClientConfig::default()
+///     .timeout(Duration::from_secs(30))
+///     .max_retries(3);
+/// let dl = DeepL::with_config(&std::env::var("DEEPL_API_KEY").unwrap(), config).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct ClientConfig {
+    /// Timeout applied to each request. Defaults to the HTTP client's default.
+    pub timeout: Option<Duration>,
+    /// Maximum number of times a request that failed with a transient error is retried.
+    /// Defaults to 0.
+    pub max_retries: u32,
+    /// Limit on the total time spent on an operation across all retry attempts
+    pub operation_deadline: Option<Duration>,
+    /// Value of the User-Agent header, e.g. "my-app/1.2.3". Defaults to the crate name
+    /// and version.
+    pub user_agent: Option<String>,
+    /// Interval at which document status is polled when not given by the caller.
+    /// Defaults to 1 second.
+    pub poll_interval: Duration,
+    /// Proxy requests are sent through. Defaults to the HTTP client's default, which
+    /// honors the system proxy environment variables.
+    pub proxy: Option<reqwest::Proxy>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            max_retries: 0,
+            operation_deadline: None,
+            user_agent: None,
+            poll_interval: Duration::from_secs(1),
+            proxy: None,
+        }
+    }
+}
+
+impl ClientConfig {
+    /// Sets the request timeout
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the maximum number of retries
+    #[must_use]
+    pub fn max_retries(mut self, max: u32) -> Self {
+        self.max_retries = max;
+        self
+    }
+
+    /// Sets the operation deadline
+    #[must_use]
+    pub fn operation_deadline(mut self, deadline: Duration) -> Self {
+        self.operation_deadline = Some(deadline);
+        self
+    }
+
+    /// Sets the User-Agent header value
+    #[must_use]
+    pub fn user_agent(mut self, app: String) -> Self {
+        self.user_agent = Some(app);
+        self
+    }
+
+    /// Sets the document poll interval
+    #[must_use]
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Sets the proxy
+    #[must_use]
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Builds an HTTP client with the timeout and proxy of this config
+    fn build_client(&self) -> Result<reqwest::blocking::Client> {
+        let mut builder = reqwest::blocking::Client::builder();
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(proxy) = self.proxy.clone() {
+            builder = builder.proxy(proxy);
+        }
+        Ok(builder.build()?)
+    }
 }
 
 /// Crate Result type
@@ -96,17 +255,37 @@ pub enum Error {
     #[error("{0}")]
     Client(String),
     /// Error sent from the server
-    #[error("{0}: {1}")]
-    Server(StatusCode, String),
-    /// Error deserializing response
-    #[error("error deserializing response")]
-    Deserialize,
+    #[error("{status}: {message}{}", code.as_ref().map(|c| format!(" ({c})")).unwrap_or_default())]
+    Server {
+        /// HTTP status of the response
+        status: StatusCode,
+        /// Error message, including the detail sent by DeepL, if any
+        message: String,
+        /// Machine-readable error code, if provided
+        code: Option<String>,
+    },
+    /// Error deserializing response, with the underlying error message
+    #[error("error deserializing response: {0}")]
+    Deserialize(String),
     /// Invalid request
     #[error("invalid request {0}")]
-    Reqwest(reqwest::Error),
+    Reqwest(#[from] reqwest::Error),
     /// Io
     #[error("{0}")]
-    Io(io::Error),
+    Io(#[from] io::Error),
+    /// Document translation failed
+    #[error("document translation failed: {0}")]
+    Document(String),
+    /// Invalid glossary entry, with the line number and content of the offending line
+    #[error("invalid glossary entry on line {0}: {1}")]
+    GlossaryEntry(usize, String),
+    /// The number of translations received doesn't match the number of texts sent
+    #[error("expected {0} translations, received {1}")]
+    TranslationCount(usize, usize),
+    /// Too many requests (429), with the delay the server asked to wait before retrying,
+    /// if given in the Retry-After header
+    #[error("too many requests")]
+    RateLimited(Option<Duration>),
     /// Invalid language
     #[error("invalid language")]
     InvalidLanguage,
@@ -115,14 +294,47 @@ pub enum Error {
     InvalidResponse,
 }
 
+impl Error {
+    /// Whether the error is likely transient, such that retrying the request may succeed.
+    ///
+    /// This is the case for rate limiting (429), server errors (500, 502, 503, 529),
+    /// and timeouts or connection errors while sending a request.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::RateLimited(_) => true,
+            Self::Server { status, .. } => matches!(status.as_u16(), 429 | 500 | 502 | 503 | 529),
+            Self::Reqwest(e) => e.is_timeout() || e.is_connect(),
+            _ => false,
+        }
+    }
+
+    /// The machine-readable error code sent by DeepL with an [`Error::Server`], if any
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            Self::Server { code, .. } => code.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Creates an [`Error::Deserialize`] from a failure to decode a response body. The
+    /// message includes the serde error, e.g. naming a missing field.
+    pub(crate) fn deserialize(e: reqwest::Error) -> Self {
+        Self::Deserialize(e.to_string())
+    }
+}
+
 /// Server error type
 #[derive(Debug, Deserialize)]
 struct ServerError {
     message: String,
+    #[serde(default)]
+    detail: Option<String>,
+    #[serde(default)]
+    code: Option<String>,
 }
 
 /// API usage & account limits. Currently assumes an individual developer account.
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct Usage {
     /// Characters translated so far in the current billing period
     pub character_count: u64,
@@ -130,7 +342,125 @@ pub struct Usage {
     pub character_limit: u64,
 }
 
-/// Self-implementing type builder
+impl Usage {
+    /// Characters that can still be translated in the current billing period.
+    /// Returns 0 if the count exceeds the limit.
+    pub fn characters_remaining(&self) -> u64 {
+        self.character_limit.saturating_sub(self.character_count)
+    }
+
+    /// Fraction of the character limit used so far, where `1.0` means the limit is reached.
+    /// May exceed `1.0` if the account is over quota, and is `1.0` if the limit is 0.
+    pub fn fraction_used(&self) -> f64 {
+        if self.character_limit == 0 {
+            return 1.0;
+        }
+        self.character_count as f64 / self.character_limit as f64
+    }
+
+    /// Whether translating `additional_chars` more characters would go over the limit.
+    ///
+    /// Only the text being translated counts toward the limit. Other request
+    /// parameters are not billed, so pair this with
+    /// [`TextOptions::estimated_characters`] to check a batch before sending it:
+    ///
+    /// ```no_run
+    /// use deeprl::{DeepL, Language, TextOptions};
+    ///
+    /// let dl = DeepL::new(&std::env::var("DEEPL_API_KEY").unwrap());
+    /// let opt = TextOptions::new(Language::DE).texts(["good morning", "good night"]);
+    ///
+    /// if !dl.usage().unwrap().would_exceed(opt.estimated_characters()) {
+    ///     dl.translate(opt, vec![]).unwrap();
+    /// }
+    /// ```
+    pub fn would_exceed(&self, additional_chars: u64) -> bool {
+        self.character_count.saturating_add(additional_chars) > self.character_limit
+    }
+
+    /// The current billing period as a `(start, end)` pair.
+    ///
+    /// The usage endpoint does not report the billing period, so this is approximated
+    /// by the current calendar month in UTC. Accounts whose billing cycle starts on a
+    /// different day of the month will see the period reset at another time.
+    pub fn billing_period(&self) -> Option<(SystemTime, SystemTime)> {
+        let days = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs() / 86400;
+        let (year, month, _) = civil_from_days(days as i64);
+        let (next_year, next_month) = if month == 12 {
+            (year + 1, 1)
+        } else {
+            (year, month + 1)
+        };
+
+        let start = days_from_civil(year, month, 1);
+        let end = days_from_civil(next_year, next_month, 1);
+        let at = |days: i64| UNIX_EPOCH + Duration::from_secs(days as u64 * 86400);
+
+        Some((at(start), at(end)))
+    }
+}
+
+/// Converts days since the Unix epoch to a `(year, month, day)` date in the
+/// proleptic Gregorian calendar
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+/// Converts a date in the proleptic Gregorian calendar to days since the Unix epoch
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = i64::from(month);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146097 + doe - 719468
+}
+
+/// Detailed API usage & account limits, including document limits reported for some plans
+#[derive(Clone, Debug, Deserialize)]
+pub struct UsageDetailed {
+    /// Characters translated so far in the current billing period
+    pub character_count: u64,
+    /// Current maximum number of characters that can be translated per billing period
+    pub character_limit: u64,
+    /// Documents translated so far in the current billing period
+    #[serde(default)]
+    pub document_count: Option<u64>,
+    /// Current maximum number of documents that can be translated per billing period
+    #[serde(default)]
+    pub document_limit: Option<u64>,
+    /// Documents translated by all users in the team so far in the current billing period
+    #[serde(default)]
+    pub team_document_count: Option<u64>,
+    /// Current maximum number of documents that can be translated by the team per billing period
+    #[serde(default)]
+    pub team_document_limit: Option<u64>,
+}
+
+/// Checks that `app` can be sent as the User-Agent header
+fn validate_user_agent(app: &str) -> Result<()> {
+    if header::HeaderValue::from_str(app).is_err() {
+        return Err(Error::Client(format!("invalid user agent: {app:?}")));
+    }
+    Ok(())
+}
+
+/// Self-implementing type builder. Fields listed under `@list` hold a `Vec` of the given
+/// type, and their getter borrows it as a slice. Fields listed under `@private` have no
+/// setter or getter, and are set by hand-written methods.
 #[macro_export]
 macro_rules! builder {
     (
@@ -141,15 +471,24 @@ macro_rules! builder {
             @optional{
                 $($opt_field:ident: $opt_type:ty,)+
             };
+            $(@list{
+                $($list_field:ident: $list_type:ty,)+
+            };)?
+            $(@private{
+                $($priv_field:ident: $priv_type:ty,)+
+            };)?
         }
     ) => {
         use paste::paste;
 
         paste! {
             #[doc = "Options for `" [<$name>] "` translation"]
+            #[derive(Clone, Debug)]
             pub struct [<$name Options>] {
                 $($must_field: $must_type,)+
                 $($opt_field: Option<$opt_type>,)+
+                $($($list_field: Option<Vec<$list_type>>,)+)?
+                $($($priv_field: Option<$priv_type>,)+)?
             }
 
             impl [<$name Options>] {
@@ -159,6 +498,8 @@ macro_rules! builder {
                     Self {
                         $($must_field,)+
                         $($opt_field: None,)+
+                        $($($list_field: None,)+)?
+                        $($($priv_field: None,)+)?
                     }
                 }
                 $(
@@ -168,6 +509,31 @@ macro_rules! builder {
                         self
                     }
                 )+
+                $($(
+                    #[doc = "Setter for `" $list_field "`"]
+                    pub fn $list_field(mut self, $list_field: Vec<$list_type>) -> Self {
+                        self.$list_field = Some($list_field);
+                        self
+                    }
+                )+)?
+                $(
+                    #[doc = "Getter for `" $must_field "`"]
+                    pub fn [<get_ $must_field>](&self) -> &$must_type {
+                        &self.$must_field
+                    }
+                )+
+                $(
+                    #[doc = "Getter for `" $opt_field "`"]
+                    pub fn [<get_ $opt_field>](&self) -> Option<&$opt_type> {
+                        self.$opt_field.as_ref()
+                    }
+                )+
+                $($(
+                    #[doc = "Getter for `" $list_field "`"]
+                    pub fn [<get_ $list_field>](&self) -> Option<&[$list_type]> {
+                        self.$list_field.as_deref()
+                    }
+                )+)?
             }
         }
     };
@@ -175,19 +541,134 @@ macro_rules! builder {
 
 impl DeepL {
     /// Create a new instance of `DeepL` from an API key.
+    ///
+    /// ## Panics
+    ///
+    /// If the key cannot be used in an HTTP header. See [`DeepL::try_new`] for a fallible
+    /// alternative.
     pub fn new(key: &str) -> Self {
-        let base = if key.ends_with(":fx") {
+        Self::try_new(key).expect("invalid API key")
+    }
+
+    /// Create a new instance of `DeepL` from an API key.
+    ///
+    /// ## Errors
+    ///
+    /// If the key contains characters not allowed in an HTTP header, e.g. a newline.
+    pub fn try_new(key: &str) -> Result<Self> {
+        let free = key.ends_with(":fx");
+        let base = if free {
             "https://api-free.deepl.com/v2"
         } else {
             "https://api.deepl.com/v2"
         };
 
-        DeepL {
+        let auth = format!("DeepL-Auth-Key {}", &key);
+        if header::HeaderValue::from_str(&auth).is_err() {
+            return Err(Error::Client("invalid API key".to_string()));
+        }
+
+        Ok(DeepL {
             client: reqwest::blocking::Client::new(),
             url: reqwest::Url::parse(base).unwrap(),
-            user_agent: None,
-            auth: format!("DeepL-Auth-Key {}", &key),
+            free,
+            config: ClientConfig::default(),
+            auth,
+            usage_cache: Mutex::new(None),
+            languages_cache: RwLock::new(HashMap::new()),
+            glossary_cache: Mutex::new(HashMap::new()),
+            max_characters: None,
+            default_target: None,
+            dry_run: false,
+            document_slots: None,
+            transport: None,
+            on_response: None,
+        })
+    }
+
+    /// Create a new instance of `DeepL` from an API key and a [`ClientConfig`].
+    ///
+    /// ## Errors
+    ///
+    /// If the key or user agent contains characters not allowed in an HTTP header, or if
+    /// the HTTP client can't be built.
+    pub fn with_config(key: &str, config: ClientConfig) -> Result<Self> {
+        let mut dl = Self::try_new(key)?;
+        if let Some(app) = &config.user_agent {
+            validate_user_agent(app)?;
+        }
+
+        if config.timeout.is_some() || config.proxy.is_some() {
+            dl.client = config.build_client()?;
+        }
+        dl.config = config;
+
+        Ok(dl)
+    }
+
+    /// Whether the API key belongs to a DeepL API Free account, as indicated by the
+    /// `:fx` suffix of the key
+    pub fn is_free_account(&self) -> bool {
+        self.free
+    }
+
+    /// The base url requests are sent to, e.g. `https://api-free.deepl.com/v2`
+    pub fn base_url(&self) -> &str {
+        self.url.as_str()
+    }
+
+    /// Sets the base url requests are sent to, e.g. to use a proxy or a mock server.
+    ///
+    /// ## Errors
+    ///
+    /// If `url` is not a valid absolute url.
+    pub fn set_base_url(&mut self, url: &str) -> Result<&mut Self> {
+        self.url = reqwest::Url::parse(url)
+            .map_err(|_| Error::Client(format!("invalid base url: {url}")))?;
+        Ok(self)
+    }
+
+    /// The client configuration
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    /// A human readable summary of the client's effective settings, one per line, for
+    /// debugging configuration. The API key is redacted.
+    pub fn config_summary(&self) -> String {
+        fn opt<T: std::fmt::Debug>(value: Option<T>) -> String {
+            value.map_or("none".to_string(), |v| format!("{v:?}"))
         }
+
+        let plan = if self.free { "free" } else { "pro" };
+        let user_agent = self.config.user_agent.as_deref().unwrap_or(APP_USER_AGENT);
+        let max_documents = self.document_slots.as_ref().map(doc::Semaphore::limit);
+
+        [
+            format!("base_url: {}", self.url),
+            format!("key: <redacted> ({plan})"),
+            format!("user_agent: {user_agent}"),
+            format!("timeout: {}", opt(self.config.timeout)),
+            format!("max_retries: {}", self.config.max_retries),
+            format!(
+                "operation_deadline: {}",
+                opt(self.config.operation_deadline)
+            ),
+            format!("poll_interval: {:?}", self.config.poll_interval),
+            format!(
+                "proxy: {}",
+                if self.config.proxy.is_some() {
+                    "set"
+                } else {
+                    "none"
+                }
+            ),
+            format!("max_characters_per_call: {}", opt(self.max_characters)),
+            format!("max_concurrent_documents: {}", opt(max_documents)),
+            format!("default_target: {}", opt(self.default_target)),
+            format!("dry_run: {}", self.dry_run),
+        ]
+        .join("\n")
     }
 
     /// Sets a user-defined HTTP client
@@ -196,12 +677,182 @@ impl DeepL {
         self
     }
 
+    /// Sends requests through `proxy`, rebuilding the HTTP client with the timeout and
+    /// proxy of the current [`ClientConfig`].
+    ///
+    /// Note: this replaces a client set with [`client`](Self::client). To combine a proxy
+    /// with other client settings, configure the proxy on that client instead.
+    ///
+    /// ## Errors
+    ///
+    /// If the HTTP client can't be built.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::*;
+    /// # let mut dl = DeepL::new(&std::env::var("DEEPL_API_KEY").unwrap());
+    /// let proxy = reqwest::Proxy::https("http://proxy.example.com:8080").unwrap();
+    /// dl.set_proxy(proxy).unwrap();
+    /// ```
+    pub fn set_proxy(&mut self, proxy: reqwest::Proxy) -> Result<&mut Self> {
+        self.config.proxy = Some(proxy);
+        self.client = self.config.build_client()?;
+        Ok(self)
+    }
+
+    /// Sets a custom [`Transport`] used to send requests in place of the HTTP client
+    pub fn transport<T>(&mut self, transport: T) -> &mut Self
+    where
+        T: Transport + 'static,
+    {
+        self.transport = Some(Box::new(transport));
+        self
+    }
+
     /// Sets app name and version to be used in the User-Agent header, e.g. "my-app/1.2.3"
-    pub fn set_app_info(&mut self, app: String) -> &mut Self {
-        self.user_agent = Some(app);
+    ///
+    /// ## Errors
+    ///
+    /// If `app` contains characters not allowed in an HTTP header, e.g. a newline.
+    pub fn set_app_info(&mut self, app: String) -> Result<&mut Self> {
+        validate_user_agent(&app)?;
+        self.config.user_agent = Some(app);
+        Ok(self)
+    }
+
+    /// Sets the maximum number of characters that may be sent in a single call to
+    /// [`translate`](Self::translate). Calls exceeding the budget are rejected before
+    /// sending anything to the server.
+    pub fn set_max_characters_per_call(&mut self, max: u64) -> &mut Self {
+        self.max_characters = Some(max);
+        self
+    }
+
+    /// Sets the target language used by [`translate_default`](Self::translate_default)
+    pub fn set_default_target(&mut self, lang: Language) -> &mut Self {
+        self.default_target = Some(lang);
+        self
+    }
+
+    /// Enables or disables dry run mode, which is off by default.
+    ///
+    /// In dry run mode, nothing is sent to DeepL by [`translate`](Self::translate) and
+    /// [`document_upload`](Self::document_upload). Instead, `translate` returns the input
+    /// text unchanged as its translations, and `document_upload` reads the file and returns
+    /// a placeholder [`Document`] with id and key `"dry-run"`. Options are validated as
+    /// usual. The results are not real translations, and the placeholder document is
+    /// unknown to the server. Other requests, e.g. for usage, are sent as usual.
+    pub fn set_dry_run(&mut self, on: bool) -> &mut Self {
+        self.dry_run = on;
+        self
+    }
+
+    /// Sets a callback invoked after every response from DeepL, e.g. to record metrics.
+    ///
+    /// The callback receives the path of the endpoint, the response status and the time
+    /// elapsed since the request was sent. It is not called for requests that fail
+    /// without a response, such as on a connection error. A panic in the callback is
+    /// caught and ignored, so it can't interrupt the request.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::*;
+    /// # let mut dl = DeepL::new(&std::env::var("DEEPL_API_KEY").unwrap());
+    /// dl.set_on_response(Box::new(|path, status, elapsed| {
+    ///     println!("{path} {status} {}ms", elapsed.as_millis());
+    /// }));
+    /// ```
+    pub fn set_on_response(&mut self, f: ResponseHook) -> &mut Self {
+        self.on_response = Some(f);
+        self
+    }
+
+    /// Sets the maximum number of times a request that failed with a transient error is
+    /// retried. Defaults to 0, i.e. no retries.
+    pub fn set_max_retries(&mut self, max: u32) -> &mut Self {
+        self.config.max_retries = max;
+        self
+    }
+
+    /// Sets the maximum number of document uploads that may be in progress at once
+    /// across threads sharing this client. Further uploads block until one finishes,
+    /// which helps stay within DeepL's concurrency limits. A limit of 0 is treated as 1.
+    pub fn set_max_concurrent_documents(&mut self, max: usize) -> &mut Self {
+        self.document_slots = Some(doc::Semaphore::new(max.max(1)));
         self
     }
 
+    /// Sets a limit on the total time spent on an operation across all retry attempts,
+    /// including the backoff between them. Once the next retry would exceed the deadline,
+    /// the last error is returned immediately.
+    pub fn set_operation_deadline(&mut self, deadline: Duration) -> &mut Self {
+        self.config.operation_deadline = Some(deadline);
+        self
+    }
+
+    /// Calls `f` until it succeeds, returns an error for which `retryable` is false,
+    /// or the configured number of retries or operation deadline is exhausted, backing
+    /// off exponentially between attempts, or as long as the server asked when rate limited
+    fn retry<T, F, P>(&self, retryable: P, mut f: F) -> Result<T>
+    where
+        F: FnMut() -> Result<T>,
+        P: Fn(&Error) -> bool,
+    {
+        let start = Instant::now();
+        let mut attempt = 0;
+        loop {
+            match f() {
+                Err(e) if attempt < self.config.max_retries && retryable(&e) => {
+                    let backoff = match e {
+                        Error::RateLimited(Some(delay)) => delay,
+                        _ => RETRY_BACKOFF * 2u32.pow(attempt),
+                    };
+                    if let Some(deadline) = self.config.operation_deadline {
+                        if start.elapsed() + backoff >= deadline {
+                            return Err(e);
+                        }
+                    }
+                    std::thread::sleep(backoff);
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Builds the url of an API endpoint by appending `segments` to the base url, e.g.
+    /// `["glossaries", id]`. Each segment is percent-encoded as needed.
+    fn endpoint(&self, segments: &[&str]) -> reqwest::Url {
+        let mut url = self.url.clone();
+        if let Ok(mut path) = url.path_segments_mut() {
+            // a base url with a trailing slash ends in an empty segment
+            path.pop_if_empty().extend(segments);
+        }
+        url
+    }
+
+    /// Sends a request through the transport if one is set, otherwise the client,
+    /// then reports the response to the response hook
+    fn send(&self, req: reqwest::blocking::RequestBuilder) -> Result<reqwest::blocking::Response> {
+        let req = req.build()?;
+        let path = req.url().path().to_string();
+        let start = Instant::now();
+
+        let resp = match &self.transport {
+            Some(transport) => transport.execute(req)?,
+            None => self.client.execute(req)?,
+        };
+
+        if let Some(hook) = &self.on_response {
+            let (status, elapsed) = (resp.status(), start.elapsed());
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| hook(&path, status, elapsed)));
+        }
+
+        Ok(resp)
+    }
+
     /// Calls the underlying client POST method
     fn post<U>(&self, url: U) -> reqwest::blocking::RequestBuilder
     where
@@ -228,17 +879,15 @@ impl DeepL {
 
     /// Construct default headers used in the request (User-Agent, Authorization)
     fn default_headers(&self) -> header::HeaderMap {
-        // user agent
-        let app = if let Some(s) = &self.user_agent {
-            s.clone()
-        } else {
-            APP_USER_AGENT.to_string()
-        };
+        // user agent, validated when set, falling back to the default otherwise
+        let app = self
+            .config
+            .user_agent
+            .as_deref()
+            .and_then(|s| header::HeaderValue::from_str(s).ok())
+            .unwrap_or(header::HeaderValue::from_static(APP_USER_AGENT));
         let mut map = reqwest::header::HeaderMap::new();
-        map.insert(
-            header::USER_AGENT,
-            header::HeaderValue::from_str(&app).unwrap(),
-        );
+        map.insert(header::USER_AGENT, app);
 
         // auth
         map.insert(
@@ -252,9 +901,45 @@ impl DeepL {
     ///
     /// Get account usage
     pub fn usage(&self) -> Result<Usage> {
-        let url = format!("{}/usage", self.url);
-        let resp = self.get(url).send().map_err(Error::Reqwest)?;
-        let usage: Usage = resp.json().map_err(|_| Error::Deserialize)?;
+        let url = self.endpoint(&["usage"]);
+        let resp = self.send(self.get(url))?;
+
+        if !resp.status().is_success() {
+            return convert(resp);
+        }
+
+        resp.json().map_err(Error::deserialize)
+    }
+
+    /// GET /usage
+    ///
+    /// Get account usage, including document limits if reported for the current plan
+    pub fn usage_detailed(&self) -> Result<UsageDetailed> {
+        let url = self.endpoint(&["usage"]);
+        let resp = self.send(self.get(url))?;
+
+        if !resp.status().is_success() {
+            return convert(resp);
+        }
+
+        resp.json().map_err(Error::deserialize)
+    }
+
+    /// GET /usage
+    ///
+    /// Get account usage, reusing the last result if it was fetched within `ttl`.
+    /// Useful for monitoring loops that would otherwise hit the endpoint on every tick.
+    pub fn usage_cached(&self, ttl: Duration) -> Result<Usage> {
+        let mut cache = self.usage_cache.lock().unwrap();
+
+        if let Some((fetched, usage)) = cache.as_ref() {
+            if fetched.elapsed() < ttl {
+                return Ok(usage.clone());
+            }
+        }
+
+        let usage = self.usage()?;
+        *cache = Some((Instant::now(), usage.clone()));
 
         Ok(usage)
     }
@@ -262,9 +947,27 @@ impl DeepL {
 
 /// Attempt to parse an error in case of unsuccessful request
 fn convert<T>(resp: reqwest::blocking::Response) -> Result<T> {
-    let code = resp.status();
+    let status = resp.status();
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        // only the delay-seconds form of Retry-After is supported, not an http date
+        let retry_after = resp
+            .headers()
+            .get(header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse().ok())
+            .map(Duration::from_secs);
+        return Err(Error::RateLimited(retry_after));
+    }
     let resp: ServerError = resp.json().map_err(|_| Error::InvalidResponse)?;
-    Err(Error::Server(code, resp.message))
+    let message = match resp.detail {
+        Some(detail) => format!("{}: {}", resp.message, detail),
+        None => resp.message,
+    };
+    Err(Error::Server {
+        status,
+        message,
+        code: resp.code,
+    })
 }
 
 #[cfg(test)]
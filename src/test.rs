@@ -1,7 +1,98 @@
 use super::*;
-use std::{env, io::Write, path::PathBuf, str::FromStr, thread, time::Duration};
+use std::{
+    collections::HashMap,
+    env,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::PathBuf,
+    str::FromStr,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
 
-const KEY: &'static str = env!("DEEPL_API_KEY");
+const KEY: &str = env!("DEEPL_API_KEY");
+
+/// Builds a raw HTTP response with a JSON body
+fn mock_response(status: u16, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {status} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+/// Starts a local server answering each incoming request with the next canned
+/// response, returning a base url to be used in place of the DeepL api
+fn mock_server(responses: Vec<String>) -> reqwest::Url {
+    mock_server_requests(responses).0
+}
+
+/// Like [`mock_server`], additionally returning a channel that receives
+/// each incoming request as text
+fn mock_server_requests(responses: Vec<String>) -> (reqwest::Url, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        for resp in responses {
+            let (stream, _) = listener.accept().unwrap();
+            let req = read_request(&stream);
+            // an empty response drops the connection without replying
+            (&stream).write_all(resp.as_bytes()).unwrap();
+            let _ = tx.send(req);
+        }
+    });
+
+    let url = reqwest::Url::parse(&format!("http://{addr}/v2")).unwrap();
+    (url, rx)
+}
+
+/// Consumes an incoming request so the client sees a clean response
+fn read_request(stream: &TcpStream) -> String {
+    let mut reader = BufReader::new(stream);
+    let mut req = vec![];
+    let mut len = 0;
+    let mut chunked = false;
+
+    // headers
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        req.extend_from_slice(line.as_bytes());
+        let lower = line.to_ascii_lowercase();
+        if let Some(v) = lower.strip_prefix("content-length:") {
+            len = v.trim().parse().unwrap();
+        }
+        if lower.starts_with("transfer-encoding:") && lower.contains("chunked") {
+            chunked = true;
+        }
+        if line == "\r\n" || line.is_empty() {
+            break;
+        }
+    }
+
+    // body
+    if chunked {
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let size = usize::from_str_radix(line.trim(), 16).unwrap();
+            let mut chunk = vec![0; size + 2];
+            reader.read_exact(&mut chunk).unwrap();
+            req.extend_from_slice(&chunk[..size]);
+            if size == 0 {
+                break;
+            }
+        }
+    } else {
+        let mut body = vec![0; len];
+        reader.read_exact(&mut body).unwrap();
+        req.extend_from_slice(&body);
+    }
+
+    String::from_utf8_lossy(&req).into_owned()
+}
 
 #[test]
 fn configure() {
@@ -14,7 +105,7 @@ fn configure() {
 
     let mut dl = DeepL::new(KEY);
     dl.client(client);
-    dl.set_app_info(app.to_owned());
+    dl.set_app_info(app.to_owned()).unwrap();
 
     let url = format!("{}/usage", dl.url);
     let req = dl.get(url).build().unwrap();
@@ -31,6 +122,154 @@ fn configure() {
     );
 }
 
+#[test]
+fn with_config() {
+    let body = r#"{"translations":[{"detected_source_language":"EN","text":"Hallo"}]}"#;
+    let config = ClientConfig::default()
+        .user_agent("my-app/1.2.3".to_string())
+        .timeout(Duration::from_millis(200));
+    let mut dl = DeepL::with_config(KEY, config).unwrap();
+    let (url, requests) = mock_server_requests(vec![mock_response(200, body)]);
+    dl.url = url;
+
+    dl.translate_string("hello", Language::DE).unwrap();
+    let req = requests.recv().unwrap().to_ascii_lowercase();
+    assert!(req.contains("user-agent: my-app/1.2.3"));
+
+    // a server that never responds
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    dl.url = reqwest::Url::parse(&format!("http://{}/v2", listener.local_addr().unwrap())).unwrap();
+    let res = dl.translate_string("hello", Language::DE);
+    assert!(matches!(res, Err(Error::Reqwest(e)) if e.is_timeout()));
+    drop(listener);
+
+    // user agents that can't be sent as a header are rejected
+    let config = ClientConfig::default().user_agent("my-app\n1.2.3".to_string());
+    assert!(matches!(
+        DeepL::with_config(KEY, config),
+        Err(Error::Client(_))
+    ));
+    let mut dl = DeepL::new(KEY);
+    let res = dl.set_app_info("my-app\n1.2.3".to_string());
+    assert!(matches!(res, Err(Error::Client(_))));
+    assert!(dl.config().user_agent.is_none());
+}
+
+#[test]
+fn config_summary() {
+    let key = "0123abcd-4567-89ef-0123-456789abcdef:fx";
+    let mut dl = DeepL::with_config(key, ClientConfig::default().max_retries(2)).unwrap();
+    dl.set_base_url("http://localhost:8080/v2").unwrap();
+    dl.set_max_concurrent_documents(4);
+
+    let summary = dl.config_summary();
+    assert!(summary.contains("base_url: http://localhost:8080/v2"));
+    assert!(summary.contains("key: <redacted> (free)"));
+    assert!(summary.contains("max_retries: 2"));
+    assert!(summary.contains("max_concurrent_documents: 4"));
+    assert!(summary.contains("timeout: none"));
+    assert!(!summary.contains("0123abcd"));
+
+    assert!(matches!(
+        dl.set_base_url("not a url"),
+        Err(Error::Client(_))
+    ));
+    assert_eq!(dl.base_url(), "http://localhost:8080/v2");
+}
+
+#[test]
+fn set_proxy() {
+    let body = r#"{"character_count":180118,"character_limit":1250000}"#;
+    let (proxy_url, requests) = mock_server_requests(vec![mock_response(200, body)]);
+
+    let mut dl = DeepL::new(KEY);
+    dl.set_base_url("http://deepl.invalid/v2").unwrap();
+    dl.set_proxy(reqwest::Proxy::http(proxy_url.as_str()).unwrap())
+        .unwrap();
+    assert!(dl.config_summary().contains("proxy: set"));
+
+    assert_eq!(dl.usage().unwrap().character_count, 180118);
+
+    // requests to a proxy carry the absolute url of the target
+    let req = requests.recv().unwrap();
+    assert!(req.starts_with("GET http://deepl.invalid/v2/usage HTTP/1.1"));
+}
+
+#[test]
+fn endpoint_urls() {
+    let body = r#"{"character_count":180118,"character_limit":1250000}"#;
+    let info = r#"{"glossary_id":"a/b c","ready":true,"name":"my_glossary","source_lang":"en","target_lang":"it","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1}"#;
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) =
+        mock_server_requests(vec![mock_response(200, body), mock_response(200, info)]);
+
+    // a trailing slash on the base url doesn't change the endpoint
+    dl.set_base_url(&format!("{url}/")).unwrap();
+    dl.usage().unwrap();
+    assert!(requests.recv().unwrap().starts_with("GET /v2/usage "));
+
+    // ids are percent-encoded as a single path segment
+    dl.glossary_info("a/b c").unwrap();
+    assert!(requests
+        .recv()
+        .unwrap()
+        .starts_with("GET /v2/glossaries/a%2Fb%20c "));
+}
+
+#[test]
+fn on_response() {
+    use std::sync::{Arc, Mutex};
+
+    let body = r#"{"character_count":180118,"character_limit":1250000}"#;
+    let forbidden = r#"{"message":"Forbidden"}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![
+        mock_response(200, body),
+        mock_response(403, forbidden),
+        mock_response(200, body),
+    ]);
+
+    let seen = Arc::new(Mutex::new(vec![]));
+    let log = Arc::clone(&seen);
+    dl.set_on_response(Box::new(move |path, status, _| {
+        log.lock().unwrap().push((path.to_string(), status));
+    }));
+
+    // the hook sees error responses too
+    dl.usage().unwrap();
+    assert!(dl.usage().is_err());
+    assert_eq!(
+        *seen.lock().unwrap(),
+        [
+            ("/v2/usage".to_string(), StatusCode::OK),
+            ("/v2/usage".to_string(), StatusCode::FORBIDDEN),
+        ]
+    );
+
+    // a panicking hook doesn't fail the request
+    dl.set_on_response(Box::new(|_, _, _| panic!("hook")));
+    assert_eq!(dl.usage().unwrap().character_count, 180118);
+}
+
+#[test]
+fn is_free_account() {
+    let dl = DeepL::new("abc:fx");
+    assert!(dl.is_free_account());
+    assert_eq!(dl.base_url(), "https://api-free.deepl.com/v2");
+
+    let dl = DeepL::new("abc");
+    assert!(!dl.is_free_account());
+    assert_eq!(dl.base_url(), "https://api.deepl.com/v2");
+}
+
+#[test]
+fn try_new() {
+    assert!(DeepL::try_new(KEY).is_ok());
+
+    let res = DeepL::try_new("abc\ndef:fx");
+    assert!(matches!(res, Err(Error::Client(_))));
+}
+
 #[test]
 fn usage() {
     let dl = DeepL::new(KEY);
@@ -42,6 +281,114 @@ fn usage() {
     assert!(usage.character_limit > 0);
 }
 
+#[test]
+fn rephrase() {
+    let body = r#"{"improvements":[{"text":"I need the report as soon as possible.","target_language":"en-US","detected_source_language":"en"}]}"#;
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) = mock_server_requests(vec![mock_response(200, body)]);
+    dl.url = url;
+
+    let opt = WriteOptions::new(Language::ENUS)
+        .writing_style(WritingStyle::Business)
+        .text(vec!["i need the report asap".to_string()]);
+    let res = dl.rephrase(opt).unwrap();
+    assert_eq!(
+        res.improvements[0].text,
+        "I need the report as soon as possible."
+    );
+
+    let req = requests.recv().unwrap();
+    assert!(req.starts_with("POST /v2/write/rephrase"));
+    assert!(req.contains("writing_style=business"));
+    assert!(req.contains("target_lang=EN-US"));
+
+    // style and tone are mutually exclusive
+    let opt = WriteOptions::new(Language::ENUS)
+        .writing_style(WritingStyle::Casual)
+        .tone(Tone::Confident)
+        .text(vec!["hello".to_string()]);
+    assert!(matches!(dl.rephrase(opt), Err(Error::Client(_))));
+    assert_eq!(Tone::Confident.as_ref(), "confident");
+}
+
+#[test]
+fn usage_remaining() {
+    let usage = Usage {
+        character_count: 500000,
+        character_limit: 500000,
+    };
+    assert_eq!(usage.characters_remaining(), 0);
+    assert_eq!(usage.fraction_used(), 1.0);
+
+    let usage = Usage {
+        character_count: 600000,
+        character_limit: 500000,
+    };
+    assert_eq!(usage.characters_remaining(), 0);
+
+    let usage = Usage {
+        character_count: 125000,
+        character_limit: 500000,
+    };
+    assert_eq!(usage.characters_remaining(), 375000);
+    assert_eq!(usage.fraction_used(), 0.25);
+    assert!(!usage.would_exceed(375000));
+    assert!(usage.would_exceed(375001));
+    assert!(usage.would_exceed(u64::MAX));
+}
+
+#[test]
+fn usage_billing_period() {
+    let usage = Usage {
+        character_count: 0,
+        character_limit: 500000,
+    };
+    let (start, end) = usage.billing_period().unwrap();
+    let now = std::time::SystemTime::now();
+    assert!(start < end);
+    assert!(start <= now);
+    assert!(end > now);
+
+    // a calendar month is 28 to 31 days
+    let len = end.duration_since(start).unwrap().as_secs() / 86400;
+    assert!((28..=31).contains(&len));
+
+    assert_eq!(civil_from_days(0), (1970, 1, 1));
+    assert_eq!(civil_from_days(19782), (2024, 2, 29));
+    assert_eq!(days_from_civil(2024, 3, 1), 19783);
+}
+
+#[test]
+fn usage_cached() {
+    // the server only answers once, so a second fetch would fail
+    let body = r#"{"character_count":42,"character_limit":500000}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(200, body)]);
+
+    let ttl = Duration::from_secs(60);
+    let first = dl.usage_cached(ttl).unwrap();
+    let second = dl.usage_cached(ttl).unwrap();
+    assert_eq!(first.character_count, 42);
+    assert_eq!(second.character_count, 42);
+
+    // an expired entry is refetched
+    assert!(dl.usage_cached(Duration::ZERO).is_err());
+}
+
+#[test]
+fn usage_detailed() {
+    let body = r#"{"character_count":180118,"character_limit":1250000,"document_count":5,"document_limit":10}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(200, body)]);
+
+    let usage = dl.usage_detailed().unwrap();
+    assert_eq!(usage.character_count, 180118);
+    assert_eq!(usage.document_count, Some(5));
+    assert_eq!(usage.document_limit, Some(10));
+    assert!(usage.team_document_count.is_none());
+    assert!(usage.team_document_limit.is_none());
+}
+
 #[test]
 fn languages() {
     let dl = DeepL::new(KEY);
@@ -80,193 +427,2138 @@ fn languages() {
 }
 
 #[test]
-fn translate_text() {
-    let dl = DeepL::new(KEY);
-
-    let src = Language::EN;
+fn languages_map() {
+    let body = r#"[
+        {"language":"DE","name":"German","supports_formality":true},
+        {"language":"XX","name":"Unknown","supports_formality":false}
+    ]"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(200, body)]);
 
-    let text = vec!["good morning".to_string()];
-    let opt = TextOptions::new(Language::DE).source_lang(src);
+    let map = dl.languages_map(LanguageType::Target).unwrap();
+    assert_eq!(map.len(), 1);
 
-    let resp = dl.translate(opt, text);
-    assert!(resp.is_ok());
-    let result = resp.unwrap();
-    let translation = &result.translations[0];
-    assert!(!translation.text.is_empty());
+    let info = &map[&Language::DE];
+    assert_eq!(info.name, "German");
+    assert_eq!(info.supports_formality, Some(true));
 }
 
 #[test]
-fn translate_options() {
-    let dl = DeepL::new(KEY);
+fn language_source_target_only() {
+    assert!(Language::EN.is_source_only());
+    assert!(Language::PT.is_source_only());
+    assert!(!Language::EN.is_target_only());
 
-    let opt = TextOptions::new(Language::FR)
-        .source_lang(Language::EN)
-        .split_sentences(SplitSentences::None)
-        .preserve_formatting(true)
-        .formality(Formality::PreferLess);
+    assert!(Language::ENGB.is_target_only());
+    assert!(Language::PTBR.is_target_only());
+    assert!(!Language::ENGB.is_source_only());
 
-    // newline in the text string
-    // lowercase, no punctuation
-    // less formal
-    let text = vec!["you\nare nice".to_string()];
-    let expect = "tu es gentille";
+    assert!(!Language::DE.is_source_only());
+    assert!(!Language::DE.is_target_only());
+}
 
-    let resp = dl.translate(opt, text).unwrap();
-    let translation = &resp.translations[0];
+#[test]
+fn languages_all() {
+    let source = r#"[{"language":"DE","name":"German"},{"language":"EN","name":"English"},{"language":"BG","name":"Bulgarian"}]"#;
+    let target = r#"[{"language":"DE","name":"German","supports_formality":true},{"language":"EN-GB","name":"English (British)","supports_formality":false}]"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(200, source), mock_response(200, target)]);
 
-    assert_eq!(translation.text, expect);
+    let langs = dl.languages_all().unwrap();
+    let codes: Vec<&str> = langs.iter().map(|l| l.language.as_str()).collect();
+    assert_eq!(codes, ["BG", "DE", "EN", "EN-GB"]);
+    assert_eq!(langs[1].supports_formality, Some(true));
 }
 
 #[test]
-fn translate_tags() {
-    let dl = DeepL::new(KEY);
+fn supports_formality_cached() {
+    let target = r#"[{"language":"DE","name":"German","supports_formality":true},{"language":"ZH","name":"Chinese","supports_formality":false}]"#;
+    let mut dl = DeepL::new(KEY);
+    // only one response is served, so a second fetch would fail
+    dl.url = mock_server(vec![mock_response(200, target)]);
 
-    let xml = r"
-<xml>
-    <head>
-        <title>My English title</title>
-    </head>
-    <body>
-        <p>Do you speak French?</p>
-        <p>The red crab</p>
-    </body>
-</xml>"
-        .to_string();
+    assert!(dl.supports_formality(Language::DE).unwrap());
+    assert!(!dl.supports_formality(Language::ZH).unwrap());
+    assert!(!dl.supports_formality(Language::JA).unwrap());
+    assert!(!dl.supports_formality(Language::EN).unwrap());
+}
 
-    let text = vec![xml];
-    let split = "p".to_string();
-    let ignore = "title".to_string();
+#[test]
+fn languages_cached() {
+    let first = r#"[{"language":"DE","name":"German"}]"#;
+    let second = r#"[{"language":"DE","name":"German"},{"language":"UK","name":"Ukrainian"}]"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(200, first), mock_response(200, second)]);
 
-    let opt = TextOptions::new(Language::FR)
-        .source_lang(Language::EN)
-        .tag_handling(TagHandling::Xml)
-        .outline_detection(false)
-        .splitting_tags(split)
-        .ignore_tags(ignore);
+    assert_eq!(dl.languages(LanguageType::Source).unwrap().len(), 1);
+    assert_eq!(dl.languages(LanguageType::Source).unwrap().len(), 1);
 
-    let resp = dl.translate(opt, text).unwrap();
-    let text = &resp.translations[0].text;
-    assert!(text.contains("<title>My English title</title>"));
-    assert!(text.contains("<p>Parlez-vous français ?</p>"));
-    assert!(text.contains("<p>Le crabe rouge</p>"));
+    dl.refresh_languages();
+    assert_eq!(dl.languages(LanguageType::Source).unwrap().len(), 2);
 }
 
 #[test]
-fn document() {
-    let dl = DeepL::new(KEY);
+fn language_round_trip() {
+    // `all` is generated along with the enum, so it covers every variant
+    let codes: std::collections::HashSet<String> =
+        Language::iter().map(|lang| lang.to_string()).collect();
+    assert_eq!(codes.len(), Language::all().len());
 
-    // create file
-    let text = "good morning".to_string();
-    let path = PathBuf::from("gm.txt");
-    std::fs::write(&path, text).unwrap();
+    for lang in Language::iter() {
+        assert_eq!(Language::from_str(lang.as_ref()).unwrap(), lang);
+        assert_eq!(lang.to_string().parse::<Language>().unwrap(), lang);
+        assert_eq!(
+            Language::from_str(&lang.to_string().to_lowercase()).unwrap(),
+            lang
+        );
+    }
+}
 
-    // test upload
-    let lang = Language::DE;
-    let opt = DocumentOptions::new(lang, path);
-    let doc_resp = dl.document_upload(opt);
-    assert!(doc_resp.is_ok());
-    let doc = doc_resp.unwrap();
-    assert!(!doc.document_id.is_empty());
+#[test]
+fn language_names() {
+    assert_eq!(Language::DE.name(), "German");
+    assert_eq!(Language::PTBR.name(), "Portuguese (Brazilian)");
+    assert_eq!(Language::DE.native_name(), "Deutsch");
+    assert_eq!(Language::JA.native_name(), "日本語");
+}
 
-    // test status
-    let mut delay = Duration::from_millis(64);
-    while !dl.document_status(&doc).unwrap().is_done() {
-        // try again after 3 sec
-        thread::sleep(delay);
-        delay *= 2;
-    }
+#[test]
+fn language_serde() {
+    let lang: Language = serde_json::from_str(r#""PT-BR""#).unwrap();
+    assert_eq!(lang, Language::PTBR);
+    let lang: Language = serde_json::from_str(r#""en-gb""#).unwrap();
+    assert_eq!(lang, Language::ENGB);
+    assert!(serde_json::from_str::<Language>(r#""XX""#).is_err());
 
-    // test download
-    let out_file = PathBuf::from("de.txt");
-    let result = dl.document_download(doc, Some(out_file.clone()));
-    assert!(result.is_ok());
+    assert_eq!(
+        serde_json::to_string(&Language::ENUS).unwrap(),
+        r#""EN-US""#
+    );
 
-    let content = std::fs::read_to_string(out_file).unwrap();
-    assert_eq!(content, "Guten Morgen");
+    #[derive(serde::Deserialize)]
+    struct Config {
+        target_lang: Language,
+    }
+    let config: Config = serde_json::from_str(r#"{"target_lang":"DE"}"#).unwrap();
+    assert_eq!(config.target_lang, Language::DE);
 }
 
 #[test]
-fn glossary_pairs() {
-    // get supported glossary language pairs
-    let dl = DeepL::new(KEY);
+fn language_all() {
+    let all = Language::all();
+    assert_eq!(all.len(), 33);
+    assert_eq!(all[0], Language::BG);
 
-    let result = dl.glossary_languages().unwrap();
-    let pairs = result.supported_languages;
-    assert!(!pairs.is_empty());
-    let first = &pairs[0];
-    assert!(!first.source_lang.is_empty());
+    let sources = Language::all_sources();
+    let targets = Language::all_targets();
+    assert!(targets.contains(&Language::ENGB));
+    assert!(!sources.contains(&Language::ENGB));
+    assert!(sources.contains(&Language::EN));
+    assert!(!targets.contains(&Language::EN));
+    assert!(sources.contains(&Language::DE) && targets.contains(&Language::DE));
+    assert_eq!(sources.len() + 4, all.len());
+    assert_eq!(targets.len() + 2, all.len());
 }
 
 #[test]
-fn glossaries() {
-    // list available glossaries
-    let dl = DeepL::new(KEY);
-
-    let result = dl.glossaries().unwrap();
-    let glossaries = result.glossaries;
-    if !glossaries.is_empty() {
-        let glos = &glossaries[0];
-        assert!(glos.entry_count > 0);
-    }
+fn language_codes() {
+    assert_eq!(Language::DE.iso639_1(), "de");
+    assert_eq!(Language::ENGB.iso639_1(), "en");
+    assert_eq!(Language::ENGB.bcp47(), "en-GB");
+    assert_eq!(Language::PTBR.bcp47(), "pt-BR");
+    assert_eq!(Language::ZH.bcp47(), "zh");
 }
 
 #[test]
-fn glossary_all() {
+fn translate_text() {
     let dl = DeepL::new(KEY);
 
-    // create csv file with two glossary entries
-    let entry = "hello,ciao\n".to_string();
-    std::fs::write("glos.csv", entry).unwrap();
-
-    let entry = "goodbye,ciao\n".to_string();
-    let _wrote = std::fs::OpenOptions::new()
-        .append(true)
-        .open("glos.csv")
-        .unwrap()
-        .write(entry.as_bytes())
-        .unwrap();
-
-    // test create glossary
-    let name = "my_glossary".to_string();
     let src = Language::EN;
-    let trg = Language::IT;
-    let entries = std::fs::read_to_string("glos.csv").unwrap();
-    let fmt = GlossaryEntriesFormat::Csv;
 
-    let glossary = dl.glossary_new(name, src, trg, entries, fmt).unwrap();
-    assert_eq!(glossary.entry_count, 2);
-    // TODO: `assert!(glossary.ready);` before attempting to use it
+    let text = vec!["good morning".to_string()];
+    let opt = TextOptions::new(Language::DE).source_lang(src);
 
-    // test fetch entries
-    let glos_id = glossary.glossary_id;
-    let resp = dl.glossary_entries(&glos_id);
+    let resp = dl.translate(opt, text);
     assert!(resp.is_ok());
-    let entries = resp.unwrap();
-    assert_eq!(entries.len(), 2);
+    let result = resp.unwrap();
+    let translation = &result.translations[0];
+    assert!(!translation.text.is_empty());
+}
 
-    // test translate with glossary
-    let opts = TextOptions::new(Language::IT)
-        .source_lang(Language::EN)
-        .preserve_formatting(true)
-        .glossary_id(glos_id.clone());
+#[test]
+fn translate_source_text() {
+    let body = r#"{"translations":[{"detected_source_language":"EN","text":"Guten Morgen"}]}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(200, body)]);
 
-    let text = vec!["goodbye".to_string()];
-    let result = dl.translate(opts, text).unwrap();
-    let translations = result.translations;
-    assert_eq!(translations[0].text, "ciao");
+    let text = vec!["good morning".to_string()];
+    let result = dl.translate(TextOptions::new(Language::DE), text).unwrap();
 
-    // test delete
-    let _: () = dl.glossary_delete(&glos_id).unwrap();
-    thread::sleep(Duration::from_secs(1));
+    let translation = &result.translations[0];
+    assert_eq!(translation.text, "Guten Morgen");
+    assert_eq!(translation.source_text.as_deref(), Some("good morning"));
+}
+
+#[test]
+fn translate_string() {
+    let body = r#"{"translations":[{"detected_source_language":"EN","text":"Guten Morgen"}]}"#;
+    let empty = r#"{"translations":[]}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(200, body), mock_response(200, empty)]);
+
+    let text = dl.translate_string("good morning", Language::DE).unwrap();
+    assert_eq!(text, "Guten Morgen");
+
+    let res = dl.translate_string("good morning", Language::DE);
+    assert!(matches!(res, Err(Error::InvalidResponse)));
+}
+
+#[test]
+fn translate_texts_only() {
+    let body = r#"{"translations":[
+        {"detected_source_language":"EN","text":"Guten Morgen"},
+        {"detected_source_language":"EN","text":"Gute Nacht"}
+    ]}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(200, body)]);
+
+    let text = vec!["good morning".to_string(), "good night".to_string()];
+    let texts = dl
+        .translate_texts_only(TextOptions::new(Language::DE), text)
+        .unwrap();
+    assert_eq!(texts, ["Guten Morgen", "Gute Nacht"]);
+}
+
+#[test]
+fn translate_batched() {
+    let chunk = |n: usize| {
+        let translations = vec![r#"{"detected_source_language":"EN","text":"Bonjour"}"#; n];
+        mock_response(
+            200,
+            &format!(r#"{{"translations":[{}]}}"#, translations.join(",")),
+        )
+    };
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![chunk(50), chunk(50), chunk(20)]);
+
+    let texts: Vec<String> = (0..120).map(|i| format!("hello {i}")).collect();
+    let translations = dl
+        .translate_batched(TextOptions::new(Language::FR), texts)
+        .unwrap();
+    assert_eq!(translations.len(), 120);
+    assert_eq!(translations[119].source_text.as_deref(), Some("hello 119"));
+
+    // empty input sends no request
+    let translations = dl
+        .translate_batched(TextOptions::new(Language::FR), vec![])
+        .unwrap();
+    assert!(translations.is_empty());
+}
+
+#[test]
+fn translate_regional_source() {
+    assert_eq!(Language::ENGB.base(), Language::EN);
+    assert_eq!(Language::PTBR.base(), Language::PT);
+    assert_eq!(Language::DE.base(), Language::DE);
+
+    let body = r#"{"translations":[{"detected_source_language":"EN","text":"Guten Morgen"}]}"#;
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) = mock_server_requests(vec![mock_response(200, body)]);
+    dl.url = url;
+
+    let opt = TextOptions::new(Language::DE).source_lang(Language::ENGB);
+    let text = vec!["good morning".to_string()];
+    dl.translate(opt, text).unwrap();
+
+    let req = requests.recv().unwrap();
+    assert!(req.contains("source_lang=EN&"));
+}
+
+#[test]
+fn translate_max_characters() {
+    let mut dl = DeepL::new(KEY);
+    dl.set_max_characters_per_call(10);
+
+    // rejected before sending, so no server is needed
+    let text = vec!["this text is too long".to_string()];
+    let res = dl.translate(TextOptions::new(Language::DE), text.clone());
+    assert!(matches!(res, Err(Error::Client(msg)) if msg.contains("exceeding")));
+
+    let res = dl.translate_batched(TextOptions::new(Language::DE), text);
+    assert!(matches!(res, Err(Error::Client(_))));
+}
+
+#[test]
+fn translate_texts() {
+    let body = r#"{"translations":[
+        {"detected_source_language":"EN","text":"Bonjour"},
+        {"detected_source_language":"EN","text":"Au revoir"}
+    ]}"#;
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) = mock_server_requests(vec![mock_response(200, body); 3]);
+    dl.url = url;
+
+    // &str array
+    let opt = TextOptions::new(Language::FR).texts(["hello", "goodbye"]);
+    let res = dl.translate(opt, vec![]).unwrap();
+    assert_eq!(res.translations.len(), 2);
+    assert!(requests
+        .recv()
+        .unwrap()
+        .ends_with("text=hello&text=goodbye"));
+
+    // Vec<String>
+    let opt = TextOptions::new(Language::FR).texts(vec!["hello".to_string()]);
+    dl.translate(opt, vec!["goodbye".to_string()]).unwrap();
+    assert!(requests
+        .recv()
+        .unwrap()
+        .ends_with("text=hello&text=goodbye"));
+
+    // iterator
+    let words = "hello goodbye".split(' ');
+    let opt = TextOptions::new(Language::FR).texts(words);
+    dl.translate(opt, vec![]).unwrap();
+    assert!(requests
+        .recv()
+        .unwrap()
+        .ends_with("text=hello&text=goodbye"));
+
+    // nothing to translate
+    let res = dl.translate(
+        TextOptions::new(Language::FR).texts(Vec::<String>::new()),
+        vec![],
+    );
+    assert!(matches!(res, Err(Error::Client(_))));
+}
+
+#[test]
+fn translate_count_mismatch() {
+    let body = r#"{"translations":[{"detected_source_language":"EN","text":"Bonjour"}]}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(200, body); 2]);
+
+    let text = vec!["hello".to_string(), "goodbye".to_string()];
+    let res = dl.translate(TextOptions::new(Language::FR), text.clone());
+    assert!(matches!(res, Err(Error::TranslationCount(2, 1))));
+
+    let res = dl.translate_batched(TextOptions::new(Language::FR), text);
+    assert!(matches!(res, Err(Error::TranslationCount(2, 1))));
+}
+
+#[test]
+fn split_sentences_values() {
+    let cases = [
+        (SplitSentences::None, "0"),
+        (SplitSentences::Default, "1"),
+        (SplitSentences::NoNewlines, "nonewlines"),
+    ];
+    let body = r#"{"translations":[{"detected_source_language":"EN","text":"Hallo"}]}"#;
+
+    for (ss, value) in cases {
+        assert_eq!(ss.as_ref(), value);
+
+        let mut dl = DeepL::new(KEY);
+        let (url, requests) = mock_server_requests(vec![mock_response(200, body)]);
+        dl.url = url;
+
+        let opt = TextOptions::new(Language::DE).split_sentences(ss);
+        dl.translate(opt, vec!["Hello".to_string()]).unwrap();
+        let req = requests.recv().unwrap();
+        assert!(req.contains(&format!("split_sentences={value}&")));
+    }
+}
+
+#[test]
+fn split_sentences_with_tag_handling() {
+    let body = r#"{"translations":[{"detected_source_language":"EN","text":"<p>Hallo</p>"}]}"#;
+    let text = vec!["<p>Hello</p>".to_string()];
+
+    let opt = TextOptions::new(Language::DE).tag_handling(TagHandling::Xml);
+    assert!(opt
+        .to_request_string()
+        .contains("split_sentences=nonewlines"));
+
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) = mock_server_requests(vec![mock_response(200, body)]);
+    dl.url = url;
+    dl.translate(opt, text.clone()).unwrap();
+    assert!(requests
+        .recv()
+        .unwrap()
+        .contains("split_sentences=nonewlines&"));
+
+    // an explicit setting is kept
+    let opt = TextOptions::new(Language::DE)
+        .tag_handling(TagHandling::Xml)
+        .split_sentences(SplitSentences::Default);
+    assert!(opt.to_request_string().contains("split_sentences=1&"));
+
+    // without tag handling, split_sentences is left to the server default
+    let opt = TextOptions::new(Language::DE);
+    assert!(!opt.to_request_string().contains("split_sentences"));
+}
+
+#[test]
+fn text_enums_from_str() {
+    for ss in [
+        SplitSentences::None,
+        SplitSentences::Default,
+        SplitSentences::NoNewlines,
+    ] {
+        let s = ss.to_string();
+        assert_eq!(SplitSentences::from_str(&s).unwrap().to_string(), s);
+    }
+    for fm in [
+        Formality::Default,
+        Formality::More,
+        Formality::Less,
+        Formality::PreferMore,
+        Formality::PreferLess,
+    ] {
+        let s = fm.to_string();
+        assert_eq!(Formality::from_str(&s).unwrap().to_string(), s);
+    }
+    for th in [TagHandling::Xml, TagHandling::Html] {
+        let s = th.to_string();
+        assert_eq!(TagHandling::from_str(&s).unwrap().to_string(), s);
+    }
+
+    assert_eq!(Formality::PreferLess.to_string(), "prefer_less");
+    assert!(matches!(
+        Formality::from_str("formal"),
+        Err(Error::Client(_))
+    ));
+    assert!(matches!(
+        SplitSentences::from_str("2"),
+        Err(Error::Client(_))
+    ));
+    assert!(matches!(
+        TagHandling::from_str("XML"),
+        Err(Error::Client(_))
+    ));
+}
+
+#[test]
+fn text_options_getters() {
+    let opt = TextOptions::new(Language::FR)
+        .source_lang(Language::EN)
+        .texts(["hello"]);
+
+    assert_eq!(opt.get_target_lang(), &Language::FR);
+    assert_eq!(opt.get_source_lang(), Some(&Language::EN));
+    assert_eq!(opt.get_text(), Some(&["hello".to_string()][..]));
+    assert!(opt.get_glossary_id().is_none());
+}
+
+#[test]
+fn detect_language() {
+    let body = r#"{"translations":[{"detected_source_language":"EN","text":"Kurzer Text"}]}"#;
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) = mock_server_requests(vec![mock_response(200, body)]);
+    dl.url = url;
+
+    let text = "short text ".repeat(20);
+    let lang = dl.detect_language(&text).unwrap();
+    assert_eq!(lang, Language::EN);
+
+    // only a sample is sent
+    let req = requests.recv().unwrap();
+    let sent = req.split("text=").nth(1).unwrap();
+    assert_eq!(sent.split('+').count(), 19);
+}
+
+#[test]
+fn text_options_clone() {
+    let body = r#"{"translations":[{"detected_source_language":"EN","text":"Bonjour"}]}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(200, body); 2]);
+
+    let opt = TextOptions::new(Language::FR).formality(Formality::PreferLess);
+    let text = vec!["hello".to_string()];
+    assert!(dl.translate(opt.clone(), text.clone()).is_ok());
+    assert!(dl.translate(opt.clone(), text).is_ok());
+
+    assert!(format!("{opt:?}").contains("PreferLess"));
+}
+
+#[test]
+fn translate_default() {
+    let body = r#"{"translations":[{"detected_source_language":"EN","text":"Guten Morgen"}]}"#;
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) = mock_server_requests(vec![mock_response(200, body)]);
+    dl.url = url;
+
+    let text = vec!["good morning".to_string()];
+    let res = dl.translate_default(text.clone());
+    assert!(matches!(res, Err(Error::Client(_))));
+
+    dl.set_default_target(Language::DE);
+    let res = dl.translate_default(text).unwrap();
+    assert_eq!(res.translations[0].text, "Guten Morgen");
+    assert!(requests.recv().unwrap().contains("target_lang=DE"));
+}
+
+#[test]
+fn formality_unsupported_target() {
+    let request = |target, fm| {
+        TextOptions::new(target)
+            .formality(fm)
+            .texts(["hi"])
+            .to_request_string()
+    };
+
+    assert_eq!(
+        request(Language::ENGB, Formality::More),
+        "target_lang=EN-GB&text=hi"
+    );
+    assert_eq!(
+        request(Language::ZH, Formality::Less),
+        "target_lang=ZH&text=hi"
+    );
+    assert!(request(Language::ENGB, Formality::PreferMore).contains("formality=prefer_more"));
+    assert!(request(Language::DE, Formality::More).contains("formality=more"));
+    assert!(request(Language::ENGB, Formality::Default).contains("formality=default"));
+
+    // documents too
+    let body = r#"{"document_id":"04DE5AD98A02647D83285A36021911C6","document_key":"0CB0054F1C132C1625B392EEDE"}"#;
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) = mock_server_requests(vec![mock_response(200, body)]);
+    dl.url = url;
+    let opt = DocumentOptions::from_bytes(Language::ENUS, b"hallo".to_vec(), "hi.txt".to_string())
+        .formality(Formality::Less);
+    dl.document_upload(opt).unwrap();
+    assert!(!requests.recv().unwrap().contains("name=\"formality\""));
+}
+
+#[test]
+fn formality_applied() {
+    let opt = TextOptions::new(Language::ZH).formality(Formality::PreferLess);
+    assert!(!opt.formality_applied());
+
+    // DeepL supports formality for Japanese, so the preference applies
+    let opt = TextOptions::new(Language::JA).formality(Formality::PreferLess);
+    assert!(opt.formality_applied());
+
+    let opt = TextOptions::new(Language::ENGB);
+    assert!(opt.formality_applied());
+    assert!(!Language::ENGB.supports_formality());
+    assert!(Language::PTBR.supports_formality());
+}
+
+#[test]
+fn dry_run() {
+    let mut dl = DeepL::new(KEY);
+    // nothing listens here, so any request sent would fail
+    dl.url = mock_server(vec![]);
+    dl.set_dry_run(true);
+    assert!(dl.config_summary().contains("dry_run: true"));
+
+    let opt = TextOptions::new(Language::DE).source_lang(Language::ENGB);
+    let text = vec!["good morning".to_string(), "good night".to_string()];
+    let res = dl.translate(opt, text).unwrap();
+    assert_eq!(res.translations.len(), 2);
+    assert_eq!(res.translations[1].text, "good night");
+    assert_eq!(res.translations[1].detected_source_language, "EN");
+
+    // options are still validated
+    let res = dl.translate(TextOptions::new(Language::EN), vec!["hallo".to_string()]);
+    assert!(matches!(res, Err(Error::Client(_))));
+
+    let opt = DocumentOptions::from_bytes(Language::DE, b"hi".to_vec(), "hi.txt".to_string());
+    let doc = dl.document_upload(opt).unwrap();
+    assert_eq!(
+        doc,
+        Document::new("dry-run".to_string(), "dry-run".to_string())
+    );
+
+    let opt = DocumentOptions::new(Language::DE, PathBuf::from("does-not-exist.txt"));
+    assert!(matches!(dl.document_upload(opt), Err(Error::Io(_))));
+
+    dl.set_dry_run(false);
+    assert!(dl.translate_string("hello", Language::DE).is_err());
+}
+
+#[test]
+fn translate_with_glossary() {
+    let info = r#"{"glossary_id":"def3a26b-3e84-45b3-84ae-0c0aaf3525f7","ready":true,"name":"my_glossary","source_lang":"en","target_lang":"it","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1}"#;
+    let body = r#"{"translations":[{"detected_source_language":"EN","text":"ciao mondo"}]}"#;
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) =
+        mock_server_requests(vec![mock_response(200, info), mock_response(200, body)]);
+    dl.url = url;
+
+    let id = "def3a26b-3e84-45b3-84ae-0c0aaf3525f7";
+    let res = dl
+        .translate_with_glossary(id, vec!["hello world".to_string()])
+        .unwrap();
+    assert_eq!(res.translations[0].text, "ciao mondo");
+
+    let _ = requests.recv().unwrap();
+    let req = requests.recv().unwrap();
+    assert!(req.contains("target_lang=IT"));
+    assert!(req.contains("source_lang=EN"));
+    assert!(req.contains(&format!("glossary_id={id}")));
+}
+
+#[test]
+fn translate_multi() {
+    let de = r#"{"translations":[{"detected_source_language":"EN","text":"Guten Morgen"}]}"#;
+    let fr = r#"{"translations":[{"detected_source_language":"EN","text":"Bonjour"}]}"#;
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) = mock_server_requests(vec![
+        mock_response(200, de),
+        mock_response(500, r#"{"message":"Internal error"}"#),
+        mock_response(200, fr),
+    ]);
+    dl.url = url;
+
+    // options other than the target are shared by every request
+    let opt = TextOptions::new(Language::ES)
+        .source_lang(Language::EN)
+        .formality(Formality::PreferLess)
+        .tag_handling(TagHandling::Html);
+    let text = vec!["good morning".to_string()];
+    let targets = [Language::DE, Language::IT, Language::FR];
+    let results = dl.translate_multi(opt, text, &targets).unwrap();
+
+    assert_eq!(results.len(), 3);
+    assert_eq!(
+        results[&Language::DE].as_ref().unwrap()[0].text,
+        "Guten Morgen"
+    );
+    assert!(matches!(results[&Language::IT], Err(Error::Server { .. })));
+    assert_eq!(results[&Language::FR].as_ref().unwrap()[0].text, "Bonjour");
+
+    for target in ["DE", "IT", "FR"] {
+        let req = requests.recv().unwrap();
+        assert!(req.contains(&format!("target_lang={target}")));
+        assert!(req.contains("source_lang=EN"));
+        assert!(req.contains("formality=prefer_less"));
+        assert!(req.contains("tag_handling=html"));
+    }
+
+    assert!(matches!(
+        dl.translate_multi(TextOptions::new(Language::DE), vec![], &targets),
+        Err(Error::Client(_))
+    ));
+}
+
+#[test]
+fn translate_protected() {
+    let body = r#"{"translations":[{"detected_source_language":"EN","text":"Mit <x>cargo add</x> &lt;<x>deeprl</x>&gt; &amp; <x>C++</x> installieren"}]}"#;
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) = mock_server_requests(vec![mock_response(200, body)]);
+    dl.url = url;
+
+    let text = vec!["Install <deeprl> & C++ with cargo add".to_string()];
+    // overlapping terms prefer the longest match, and special characters match literally
+    let terms = ["cargo", "cargo add", "deeprl", "C++", "+"];
+    let res = dl.translate_protected(text, Language::DE, &terms).unwrap();
+    assert_eq!(
+        res.translations[0].text,
+        "Mit cargo add <deeprl> & C++ installieren"
+    );
+
+    let req = requests.recv().unwrap();
+    assert!(req.contains("tag_handling=xml"));
+    assert!(req.contains("ignore_tags=x&"));
+    // Install &lt;<x>deeprl</x>&gt; &amp; <x>C++</x> with <x>cargo add</x>
+    assert!(req.contains(
+        "text=Install+%26lt%3B%3Cx%3Edeeprl%3C%2Fx%3E%26gt%3B+%26amp%3B+%3Cx%3EC%2B%2B%3C%2Fx%3E+with+%3Cx%3Ecargo+add%3C%2Fx%3E"
+    ));
+
+    // tag handling set either before or after protecting terms is rejected
+    for opt in [
+        TextOptions::new(Language::DE)
+            .tag_handling(TagHandling::Html)
+            .protect_terms(&["deeprl"]),
+        TextOptions::new(Language::DE)
+            .protect_terms(&["deeprl"])
+            .tag_handling(TagHandling::Xml),
+    ] {
+        let res = dl.translate(opt, vec!["Install deeprl".to_string()]);
+        assert!(matches!(res, Err(Error::Client(_))));
+    }
+}
+
+#[test]
+fn translate_normalize_void_tags() {
+    let body = r#"{"translations":[{"detected_source_language":"EN","text":"<p>Zeile eins<br/>Zeile zwei <img src=\"a.png\" /></p><!-- <br> -->"}]}"#;
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) = mock_server_requests(vec![mock_response(200, body)]);
+    dl.url = url;
+
+    let opt = TextOptions::new(Language::DE)
+        .tag_handling(TagHandling::Html)
+        .normalize_void_tags(true);
+    let text = r#"<p>Line one<br>Line two <img src="a.png"></p><!-- <br> -->"#;
+    let res = dl.translate(opt, vec![text.to_string()]).unwrap();
+    assert_eq!(
+        res.translations[0].text,
+        r#"<p>Zeile eins<br>Zeile zwei <img src="a.png"></p><!-- <br> -->"#
+    );
+    assert_eq!(res.translations[0].source_text.as_deref(), Some(text));
+
+    // sent as self-closing tags, leaving the comment alone
+    let req = requests.recv().unwrap();
+    assert!(req.contains("one%3Cbr%2F%3ELine"));
+    assert!(req.contains("%22a.png%22%2F%3E"));
+    assert!(req.contains("%3C%21--+%3Cbr%3E+--%3E"));
+}
+
+#[test]
+fn translate_match_source_case() {
+    let body = r#"{"translations":[{"detected_source_language":"EN","text":"Achtung"},{"detected_source_language":"EN","text":"Hallo Welt"},{"detected_source_language":"EN","text":"Morgen ist ein neuer Tag"}]}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(200, body), mock_response(200, body)]);
+    let text = vec![
+        "WARNING".to_string(),
+        "hello world".to_string(),
+        "tomorrow is a new day".to_string(),
+    ];
+
+    let opt = TextOptions::new(Language::DE).match_source_case(true);
+    let res = dl.translate(opt, text.clone()).unwrap();
+    assert_eq!(res.translations[0].text, "ACHTUNG");
+    assert_eq!(res.translations[1].text, "hallo Welt");
+    // longer texts are left alone
+    assert_eq!(res.translations[2].text, "Morgen ist ein neuer Tag");
+
+    let res = dl.translate(TextOptions::new(Language::DE), text).unwrap();
+    assert_eq!(res.translations[0].text, "Achtung");
+}
+
+#[test]
+fn translate_lenient() {
+    let body = r#"{"translations":[
+        {"detected_source_language":"EN","text":"Guten Morgen"},
+        {"detected_source_language":"EN"},
+        {"detected_source_language":"EN","text":"Gute Nacht"}
+    ]}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(200, body), mock_response(200, body)]);
+    let text = vec![
+        "good morning".to_string(),
+        "hello".to_string(),
+        "good night".to_string(),
+    ];
+
+    // the strict version fails on the malformed translation
+    let res = dl.translate(TextOptions::new(Language::DE), text.clone());
+    assert!(matches!(res, Err(Error::Deserialize(msg)) if msg.contains("missing field `text`")));
+
+    let res = dl
+        .translate_lenient(TextOptions::new(Language::DE), text)
+        .unwrap();
+    assert_eq!(res.skipped, 1);
+    assert_eq!(res.translations.len(), 2);
+    assert_eq!(res.translations[1].text, "Gute Nacht");
+    assert_eq!(
+        res.translations[1].source_text.as_deref(),
+        Some("good night")
+    );
+
+    // protected terms are sent wrapped and restored like in `translate`
+    let body = r#"{"translations":[
+        {"detected_source_language":"EN","text":"<x>deeprl</x> installieren"},
+        {"detected_source_language":"EN"}
+    ]}"#;
+    let (url, requests) = mock_server_requests(vec![mock_response(200, body)]);
+    dl.url = url;
+    let opt = TextOptions::new(Language::DE).protect_terms(&["deeprl"]);
+    let text = vec!["install deeprl".to_string(), "hello".to_string()];
+    let res = dl.translate_lenient(opt, text).unwrap();
+    assert_eq!(res.skipped, 1);
+    assert_eq!(res.translations[0].text, "deeprl installieren");
+    assert!(requests
+        .recv()
+        .unwrap()
+        .contains("text=install+%3Cx%3Edeeprl%3C%2Fx%3E"));
+
+    // large batches are split into chunks, here without sending them
+    dl.set_dry_run(true);
+    let text: Vec<String> = (0..120).map(|i| i.to_string()).collect();
+    let res = dl
+        .translate_lenient(TextOptions::new(Language::DE), text)
+        .unwrap();
+    assert_eq!(res.skipped, 0);
+    assert_eq!(res.translations.len(), 120);
+    assert_eq!(res.translations[119].source_text.as_deref(), Some("119"));
+}
+
+#[test]
+fn text_options_request_string() {
+    let body = r#"{"translations":[{"detected_source_language":"EN","text":"<p>Hallo</p>"}]}"#;
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) = mock_server_requests(vec![mock_response(200, body)]);
+    dl.url = url;
+
+    let opt = TextOptions::new(Language::DE)
+        .source_lang(Language::ENGB)
+        .split_sentences(SplitSentences::NoNewlines)
+        .preserve_formatting(true)
+        .formality(Formality::PreferLess)
+        .glossary_id("def3a26b-3e84-45b3-84ae-0c0aaf3525f7".to_string())
+        .tag_handling(TagHandling::Html)
+        .non_splitting_tags("b".to_string())
+        .outline_detection(false)
+        .splitting_tags("p,div".to_string())
+        .ignore_tags("code".to_string())
+        .model_type(ModelType::QualityOptimized)
+        .texts(["<p>Hello & welcome</p>"]);
+
+    let expected = "target_lang=DE&source_lang=EN&split_sentences=nonewlines\
+        &preserve_formatting=1&formality=prefer_less\
+        &glossary_id=def3a26b-3e84-45b3-84ae-0c0aaf3525f7&tag_handling=html\
+        &non_splitting_tags=b&outline_detection=0&splitting_tags=p%2Cdiv&ignore_tags=code\
+        &model_type=quality_optimized&text=%3Cp%3EHello+%26+welcome%3C%2Fp%3E";
+    assert_eq!(opt.to_request_string(), expected);
+
+    // the body sent by translate is the same
+    dl.translate(opt, vec![]).unwrap();
+    let req = requests.recv().unwrap();
+    assert!(req.ends_with(&format!("\r\n\r\n{expected}")));
+}
+
+#[test]
+fn estimated_characters() {
+    let opt = TextOptions::new(Language::DE);
+    assert_eq!(opt.estimated_characters(), 0);
+
+    // counted by code points: 5 + 3 + 2 (emoji with variation selector) + 9
+    let opt = opt.texts(["Grüße", "日本語", "❤️", "good luck"]);
+    assert_eq!(opt.estimated_characters(), 19);
+}
+
+#[test]
+fn tag_handling_guess() {
+    let xml = r#"<?xml version="1.0"?><doc><p>Hello</p></doc>"#;
+    assert!(matches!(TagHandling::guess(xml), Some(TagHandling::Xml)));
+
+    for html in [
+        "<!DOCTYPE html><html><body>Hi</body></html>",
+        "  <P>Hello</P>",
+        "Line one<br>Line two",
+        r#"See <a href="https://deepl.com">DeepL</a>"#,
+    ] {
+        assert!(matches!(TagHandling::guess(html), Some(TagHandling::Html)));
+    }
+
+    assert!(TagHandling::guess("Hello world").is_none());
+    assert!(TagHandling::guess("if a < b && b > c").is_none());
+    assert!(TagHandling::guess("<greeting>Hello</greeting>").is_none());
+}
+
+#[test]
+fn tag_list_setters() {
+    let opt = TextOptions::new(Language::DE)
+        .splitting_tags_iter(["p", "div"])
+        .non_splitting_tags_iter(vec![String::from("b")])
+        .ignore_tags_iter(["code", "pre"]);
+
+    assert_eq!(opt.get_splitting_tags().unwrap(), "p,div");
+    assert_eq!(opt.get_non_splitting_tags().unwrap(), "b");
+    assert_eq!(opt.get_ignore_tags().unwrap(), "code,pre");
+}
+
+#[test]
+fn translate_tag_options_require_tag_handling() {
+    let dl = DeepL::new(KEY);
+
+    let opt = TextOptions::new(Language::DE).ignore_tags("x".to_string());
+    let res = dl.translate(opt, vec!["<x>Hi</x> there".to_string()]);
+    assert!(matches!(res, Err(Error::Client(msg)) if msg.starts_with("ignore_tags")));
+
+    let opt = TextOptions::new(Language::DE).outline_detection(false);
+    let res = dl.translate(opt, vec!["<p>Hi</p>".to_string()]);
+    assert!(matches!(res, Err(Error::Client(msg)) if msg.contains(".tag_handling(...)")));
+}
+
+#[test]
+fn translate_model_type() {
+    let body = r#"{"translations":[{"detected_source_language":"EN","text":"Guten Morgen"}],"model_type_used":"quality_optimized"}"#;
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) = mock_server_requests(vec![mock_response(200, body)]);
+    dl.url = url;
+
+    let opt = TextOptions::new(Language::DE).model_type(ModelType::QualityOptimized);
+    let res = dl.translate(opt, vec!["good morning".to_string()]).unwrap();
+    assert_eq!(res.model_type_used.as_deref(), Some("quality_optimized"));
+    assert!(requests
+        .recv()
+        .unwrap()
+        .contains("model_type=quality_optimized"));
+
+    let mt = ModelType::from_str("prefer_quality_optimized").unwrap();
+    assert_eq!(mt.to_string(), "prefer_quality_optimized");
+}
+
+#[test]
+fn translation_detected_language() {
+    let mut translation = Translation {
+        detected_source_language: "XX".into(),
+        text: "hello".into(),
+        source_text: None,
+    };
+    assert_eq!(translation.detected_language(), None);
+
+    translation.detected_source_language = "EN".into();
+    assert_eq!(translation.detected_language(), Some(Language::EN));
+}
+
+#[test]
+fn translation_as_str() {
+    fn shout(s: impl AsRef<str>) -> String {
+        s.as_ref().to_uppercase()
+    }
+
+    let translation = Translation {
+        detected_source_language: "EN".into(),
+        text: "Guten Morgen".into(),
+        source_text: None,
+    };
+    assert_eq!(shout(&translation), "GUTEN MORGEN");
+    assert!(translation.starts_with("Guten"));
+    assert_eq!(&*translation, "Guten Morgen");
+}
+
+#[test]
+fn translate_to_file() {
+    let one = r#"{"translations":[{"detected_source_language":"EN","text":"Guten Morgen"}]}"#;
+    let two = r#"{"translations":[{"detected_source_language":"EN","text":"Guten Morgen"},{"detected_source_language":"EN","text":"Gute Nacht"}]}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(200, one), mock_response(200, two)]);
+    let path = env::temp_dir().join("deeprl-translate-to-file.txt");
+
+    let opt = TextOptions::new(Language::DE).texts(["good morning"]);
+    dl.translate_to_file(opt, &path).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "Guten Morgen");
+
+    let opt = TextOptions::new(Language::DE).texts(["good morning", "good night"]);
+    dl.translate_to_file(opt, &path).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "Guten Morgen\nGute Nacht"
+    );
+}
+
+#[test]
+fn translate_options() {
+    let dl = DeepL::new(KEY);
+
+    let opt = TextOptions::new(Language::FR)
+        .source_lang(Language::EN)
+        .split_sentences(SplitSentences::None)
+        .preserve_formatting(true)
+        .formality(Formality::PreferLess);
+
+    // newline in the text string
+    // lowercase, no punctuation
+    // less formal
+    let text = vec!["you\nare nice".to_string()];
+    let expect = "tu es gentille";
+
+    let resp = dl.translate(opt, text).unwrap();
+    let translation = &resp.translations[0];
+
+    assert_eq!(translation.text, expect);
+}
+
+#[test]
+fn translate_tags() {
+    let dl = DeepL::new(KEY);
+
+    let xml = r"
+<xml>
+    <head>
+        <title>My English title</title>
+    </head>
+    <body>
+        <p>Do you speak French?</p>
+        <p>The red crab</p>
+    </body>
+</xml>"
+        .to_string();
+
+    let text = vec![xml];
+    let split = "p".to_string();
+    let ignore = "title".to_string();
+
+    let opt = TextOptions::new(Language::FR)
+        .source_lang(Language::EN)
+        .tag_handling(TagHandling::Xml)
+        .outline_detection(false)
+        .splitting_tags(split)
+        .ignore_tags(ignore);
+
+    let resp = dl.translate(opt, text).unwrap();
+    let text = &resp.translations[0].text;
+    assert!(text.contains("<title>My English title</title>"));
+    assert!(text.contains("<p>Parlez-vous français ?</p>"));
+    assert!(text.contains("<p>Le crabe rouge</p>"));
+}
+
+#[test]
+fn translate_verify_tags() {
+    let valid =
+        r#"{"translations":[{"detected_source_language":"EN","text":"<p>Bonjour<br></p>"}]}"#;
+    let broken =
+        r#"{"translations":[{"detected_source_language":"EN","text":"<p><b>Bonjour</p></b>"}]}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(200, valid), mock_response(200, broken)]);
+
+    let opt = TextOptions::new(Language::FR)
+        .tag_handling(TagHandling::Html)
+        .verify_tags(true);
+    let text = vec!["<p>Hello<br></p>".to_string()];
+    assert!(dl.translate(opt, text).is_ok());
+
+    let opt = TextOptions::new(Language::FR)
+        .tag_handling(TagHandling::Html)
+        .verify_tags(true);
+    let text = vec!["<p><b>Hello</b></p>".to_string()];
+    let res = dl.translate(opt, text);
+    assert!(matches!(res, Err(Error::Client(_))));
+}
+
+#[test]
+fn document() {
+    let dl = DeepL::new(KEY);
+
+    // create file
+    let text = "good morning".to_string();
+    let path = PathBuf::from("gm.txt");
+    std::fs::write(&path, text).unwrap();
+
+    // test upload
+    let lang = Language::DE;
+    let opt = DocumentOptions::new(lang, path);
+    let doc_resp = dl.document_upload(opt);
+    assert!(doc_resp.is_ok());
+    let doc = doc_resp.unwrap();
+    assert!(!doc.document_id.is_empty());
+
+    // test status
+    let mut delay = Duration::from_millis(64);
+    while !dl.document_status(&doc).unwrap().is_done() {
+        // try again after 3 sec
+        thread::sleep(delay);
+        delay *= 2;
+    }
+
+    // test download
+    let out_file = PathBuf::from("de.txt");
+    let result = dl.document_download(&doc, Some(out_file.clone()));
+    assert!(result.is_ok());
+
+    let content = std::fs::read_to_string(out_file).unwrap();
+    assert_eq!(content, "Guten Morgen");
+}
+
+/// Answers every request with the same status and body
+struct MockTransport {
+    status: u16,
+    body: &'static str,
+}
+
+impl Transport for MockTransport {
+    fn execute(&self, req: reqwest::blocking::Request) -> Result<reqwest::blocking::Response> {
+        assert_eq!(req.url().path(), "/v2/usage");
+        let resp = http::Response::builder()
+            .status(self.status)
+            .body(self.body)
+            .unwrap();
+        Ok(resp.into())
+    }
+}
+
+#[test]
+fn usage_mock_transport() {
+    let mut dl = DeepL::new(KEY);
+    dl.transport(MockTransport {
+        status: 200,
+        body: r#"{"character_count":180118,"character_limit":1250000}"#,
+    });
+    let usage = dl.usage().unwrap();
+    assert_eq!(usage.character_count, 180118);
+    assert_eq!(usage.character_limit, 1250000);
+    let usage = dl.usage_detailed().unwrap();
+    assert_eq!(usage.character_count, 180118);
+
+    let mut dl = DeepL::new(KEY);
+    let transport = MockTransport {
+        status: 456,
+        body: r#"{"message":"Quota exceeded"}"#,
+    };
+    dl.transport(transport);
+    assert!(matches!(
+        dl.usage(),
+        Err(Error::Server { status, message, .. }) if status.as_u16() == 456 && message == "Quota exceeded"
+    ));
+}
+
+#[test]
+fn error_source() {
+    use std::error::Error as _;
+
+    let err = Error::from(std::io::Error::new(std::io::ErrorKind::NotFound, "gone"));
+    assert!(matches!(err, Error::Io(_)));
+    assert_eq!(err.source().unwrap().to_string(), "gone");
+
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![String::new()]);
+    let err = dl.usage().unwrap_err();
+    assert!(matches!(err, Error::Reqwest(_)));
+    assert!(err.source().is_some());
+}
+
+#[test]
+fn document_new() {
+    let status = r#"{"document_id":"04DE5AD98A02647D83285A36021911C6","status":"done","billed_characters":1337}"#;
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) = mock_server_requests(vec![mock_response(200, status)]);
+    dl.url = url;
+
+    let doc = Document::new(
+        "04DE5AD98A02647D83285A36021911C6".to_string(),
+        "0CB0054F1C132C1625B392EEDE".to_string(),
+    );
+    assert!(dl.document_status(&doc).unwrap().is_done());
+
+    let req = requests.recv().unwrap();
+    assert!(req.starts_with("POST /v2/document/04DE5AD98A02647D83285A36021911C6 "));
+    assert!(req.contains("document_key=0CB0054F1C132C1625B392EEDE"));
+}
+
+#[test]
+fn document_upload_with_timeout() {
+    // a server that takes longer to respond than the request timeout
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let _ = read_request(&stream);
+        thread::sleep(Duration::from_secs(2));
+    });
+
+    let mut dl = DeepL::new(KEY);
+    dl.url = reqwest::Url::parse(&format!("http://{addr}/v2")).unwrap();
+
+    let opt = DocumentOptions::from_bytes(Language::DE, b"hi".to_vec(), "hi.txt".to_string());
+    let res = dl.document_upload_with_timeout(opt, Duration::from_millis(100));
+    assert!(matches!(res, Err(Error::Reqwest(e)) if e.is_timeout()));
+}
+
+#[test]
+fn document_upload_params() {
+    let body = r#"{"document_id":"04DE5AD98A02647D83285A36021911C6","document_key":"0CB0054F1C132C1625B392EEDE"}"#;
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) = mock_server_requests(vec![mock_response(200, body)]);
+    dl.url = url;
+
+    let opt = DocumentOptions::from_bytes(Language::DE, b"hi".to_vec(), "hi.docx".to_string())
+        .source_lang(Language::ENUS)
+        .formality(Formality::PreferMore)
+        .glossary_id("def3a26b-3e84-45b3-84ae-0c0aaf3525f7".to_string())
+        .output_format("pdf".to_string())
+        .enable_document_minification(true);
+    dl.document_upload(opt).unwrap();
+
+    let req = requests.recv().unwrap();
+    for (name, value) in [
+        ("target_lang", "DE"),
+        ("source_lang", "EN"),
+        ("filename", "hi.docx"),
+        ("formality", "prefer_more"),
+        ("glossary_id", "def3a26b-3e84-45b3-84ae-0c0aaf3525f7"),
+        ("output_format", "pdf"),
+        ("enable_document_minification", "true"),
+    ] {
+        let part = format!("Content-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n");
+        assert!(req.contains(&part), "missing {name}");
+    }
+
+    // a glossary requires the source language
+    let opt = DocumentOptions::from_bytes(Language::DE, b"hi".to_vec(), "hi.txt".to_string())
+        .glossary_id("def3a26b-3e84-45b3-84ae-0c0aaf3525f7".to_string());
+    assert!(matches!(dl.document_upload(opt), Err(Error::Client(_))));
+}
+
+#[test]
+fn document_upload_retry() {
+    let body = r#"{"document_id":"04DE5AD98A02647D83285A36021911C6","document_key":"0CB0054F1C132C1625B392EEDE"}"#;
+    // the first attempt times out, the second succeeds
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx, requests) = mpsc::channel();
+    let resp = mock_response(200, body);
+    thread::spawn(move || {
+        // hold the first connection open without replying
+        let (first, _) = listener.accept().unwrap();
+        tx.send(read_request(&first)).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        tx.send(read_request(&stream)).unwrap();
+        (&stream).write_all(resp.as_bytes()).unwrap();
+        drop(first);
+    });
+
+    let mut dl = DeepL::new(KEY);
+    dl.url = reqwest::Url::parse(&format!("http://{addr}/v2")).unwrap();
+    dl.set_max_retries(1);
+
+    let bytes = b"good morning".to_vec();
+    let opt = DocumentOptions::from_bytes(Language::DE, bytes, "gm.txt".to_string());
+    let doc = dl
+        .document_upload_with_timeout(opt, Duration::from_millis(100))
+        .unwrap();
+    assert_eq!(doc.document_id, "04DE5AD98A02647D83285A36021911C6");
+
+    // the file is sent again in full on the second attempt
+    for _ in 0..2 {
+        let req = requests.recv().unwrap();
+        assert!(req.contains("good morning"));
+        assert!(req.contains("filename=\"gm.txt\""));
+    }
+
+    // a connection dropped after the upload was sent is not retried
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) = mock_server_requests(vec![String::new(), mock_response(200, body)]);
+    dl.url = url;
+    dl.set_max_retries(1);
+
+    let opt = DocumentOptions::from_bytes(Language::DE, b"hi".to_vec(), "hi.txt".to_string());
+    let res = dl.document_upload(opt);
+    assert!(matches!(res, Err(Error::Reqwest(e)) if e.is_request()));
+    assert!(requests.recv().is_ok());
+    assert!(requests.recv_timeout(Duration::from_millis(200)).is_err());
+
+    // client errors are not retried
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) = mock_server_requests(vec![
+        mock_response(400, r#"{"message":"Bad request"}"#),
+        mock_response(200, body),
+    ]);
+    dl.url = url;
+    dl.set_max_retries(1);
+
+    let opt = DocumentOptions::from_bytes(Language::DE, b"hi".to_vec(), "hi.txt".to_string());
+    let res = dl.document_upload(opt);
+    assert!(matches!(
+        res,
+        Err(Error::Server {
+            status: StatusCode::BAD_REQUEST,
+            ..
+        })
+    ));
+    requests.recv().unwrap();
+    assert!(requests.recv_timeout(Duration::from_millis(500)).is_err());
+}
+
+#[test]
+fn translate_operation_deadline() {
+    let body = r#"{"message":"Too many requests"}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(429, body); 10]);
+    dl.set_max_retries(10);
+    dl.set_operation_deadline(Duration::from_millis(600));
+
+    let start = std::time::Instant::now();
+    let res = dl.translate(TextOptions::new(Language::DE), vec!["hello".to_string()]);
+    assert!(matches!(res, Err(Error::RateLimited(None))));
+    assert!(start.elapsed() < Duration::from_millis(600));
+}
+
+#[test]
+fn document_upload_concurrency() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let body = r#"{"document_id":"04DE5AD98A02647D83285A36021911C6","document_key":"0CB0054F1C132C1625B392EEDE"}"#;
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let max_seen = Arc::new(AtomicUsize::new(0));
+
+    // answer requests concurrently, recording how many are in flight at once
+    let (current, max) = (in_flight.clone(), max_seen.clone());
+    let resp = mock_response(200, body);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = stream.unwrap();
+            let (current, max, resp) = (current.clone(), max.clone(), resp.clone());
+            thread::spawn(move || {
+                read_request(&stream);
+                let n = current.fetch_add(1, Ordering::SeqCst) + 1;
+                max.fetch_max(n, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(50));
+                current.fetch_sub(1, Ordering::SeqCst);
+                (&stream).write_all(resp.as_bytes()).unwrap();
+            });
+        }
+    });
+
+    let mut dl = DeepL::new(KEY);
+    dl.url = reqwest::Url::parse(&format!("http://{addr}/v2")).unwrap();
+    dl.set_max_concurrent_documents(2);
+
+    thread::scope(|s| {
+        for i in 0..6 {
+            let dl = &dl;
+            s.spawn(move || {
+                let opt =
+                    DocumentOptions::from_bytes(Language::DE, b"hi".to_vec(), format!("{i}.txt"));
+                dl.document_upload(opt).unwrap();
+            });
+        }
+    });
+
+    let max = max_seen.load(Ordering::SeqCst);
+    assert!((1..=2).contains(&max), "{max} uploads in flight");
+}
+
+#[test]
+fn document_from_bytes() {
+    let body = r#"{"document_id":"04DE5AD98A02647D83285A36021911C6","document_key":"0CB0054F1C132C1625B392EEDE"}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(200, body)]);
+
+    let bytes = b"good morning".to_vec();
+    let opt = DocumentOptions::from_bytes(Language::DE, bytes, "gm.txt".to_string());
+    let doc = dl.document_upload(opt).unwrap();
+    assert_eq!(doc.document_id, "04DE5AD98A02647D83285A36021911C6");
+}
+
+#[test]
+fn translate_document() {
+    let doc = r#"{"document_id":"04DE5AD98A02647D83285A36021911C6","document_key":"0CB0054F1C132C1625B392EEDE"}"#;
+    let queued = r#"{"document_id":"04DE5AD98A02647D83285A36021911C6","status":"queued"}"#;
+    let done = r#"{"document_id":"04DE5AD98A02647D83285A36021911C6","status":"done","billed_characters":12}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![
+        mock_response(200, doc),
+        mock_response(200, queued),
+        mock_response(200, done),
+        mock_response(200, "Guten Morgen"),
+    ]);
+
+    let opt = DocumentOptions::from_bytes(Language::DE, b"good morning".to_vec(), "gm.txt".into());
+    let out_file = env::temp_dir().join("deeprl-translate-document.txt");
+    let interval = Duration::from_millis(10);
+    let path = dl
+        .translate_document(opt, Some(out_file), interval, None)
+        .unwrap();
+    assert_eq!(std::fs::read_to_string(path).unwrap(), "Guten Morgen");
+
+    // translation error
+    let error = r#"{"document_id":"04DE5AD98A02647D83285A36021911C6","status":"error","error_message":"Source and target language are equal."}"#;
+    dl.url = mock_server(vec![mock_response(200, doc), mock_response(200, error)]);
+
+    let opt = DocumentOptions::from_bytes(Language::DE, b"Guten Morgen".to_vec(), "gm.txt".into());
+    let res = dl.translate_document(opt, None, interval, None);
+    assert!(matches!(res, Err(Error::Document(msg)) if msg.contains("language are equal")));
+}
+
+#[test]
+fn document_glossary_eq() {
+    let doc = Document {
+        document_id: "04DE5AD98A02647D83285A36021911C6".to_string(),
+        document_key: "0CB0054F1C132C1625B392EEDE".to_string(),
+    };
+    let mut in_flight = HashMap::new();
+    in_flight.insert(doc.clone(), "report.docx");
+    assert_eq!(in_flight.get(&doc), Some(&"report.docx"));
+
+    let glossary = Glossary {
+        glossary_id: "def3a26b-3e84-45b3-84ae-0c0aaf3525f7".to_string(),
+        ready: true,
+        name: "my_glossary".to_string(),
+        source_lang: "en".to_string(),
+        target_lang: "it".to_string(),
+        creation_time: "2021-08-03T14:16:18.329Z".to_string(),
+        entry_count: 1,
+    };
+    assert_eq!(glossary.clone(), glossary);
+}
+
+#[test]
+fn document_download_to() {
+    let content = "Guten Morgen\n".repeat(1000);
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(200, &content)]);
+    let doc = || Document {
+        document_id: "04DE5AD98A02647D83285A36021911C6".to_string(),
+        document_key: "0CB0054F1C132C1625B392EEDE".to_string(),
+    };
+
+    let mut buf = vec![];
+    let len = dl.document_download_to(&doc(), &mut buf).unwrap();
+    assert_eq!(len, content.len() as u64);
+    assert_eq!(buf, content.as_bytes());
+
+    dl.url = mock_server(vec![
+        mock_response(200, &content),
+        mock_response(404, r#"{"message":"Document not found"}"#),
+    ]);
+    let bytes = dl.document_download_bytes(&doc()).unwrap();
+    assert_eq!(bytes, content.as_bytes());
+
+    // nothing is written on error
+    let mut buf = vec![];
+    let res = dl.document_download_to(&doc(), &mut buf);
+    assert!(matches!(
+        res,
+        Err(Error::Server {
+            status: StatusCode::NOT_FOUND,
+            ..
+        })
+    ));
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn document_io_errors() {
+    let mut dl = DeepL::new(KEY);
+
+    // missing input file
+    let opt = DocumentOptions::new(Language::DE, PathBuf::from("deeprl-no-such-file.txt"));
+    let res = dl.document_upload(opt);
+    assert!(matches!(res, Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::NotFound));
+
+    // unwritable output path
+    dl.url = mock_server(vec![mock_response(200, "Guten Morgen")]);
+    let doc = Document {
+        document_id: "04DE5AD98A02647D83285A36021911C6".to_string(),
+        document_key: "0CB0054F1C132C1625B392EEDE".to_string(),
+    };
+    let out_file = env::temp_dir().join("deeprl-no-such-dir").join("out.txt");
+    let res = dl.document_download(&doc, Some(out_file));
+    assert!(matches!(res, Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::NotFound));
+
+    // a download cut short leaves an existing output file untouched
+    let truncated = "HTTP/1.1 200 Mock\r\nContent-Length: 100\r\nConnection: close\r\n\r\nGuten";
+    dl.url = mock_server(vec![truncated.to_string()]);
+    let out_file = env::temp_dir().join("deeprl-document-truncated.txt");
+    std::fs::write(&out_file, "previous").unwrap();
+    let res = dl.document_download(&doc, Some(out_file.clone()));
+    assert!(res.is_err());
+    assert_eq!(std::fs::read_to_string(&out_file).unwrap(), "previous");
+    assert!(!env::temp_dir()
+        .join("deeprl-document-truncated.txt.part")
+        .exists());
+    std::fs::remove_file(out_file).unwrap();
+}
+
+#[test]
+fn document_resume() {
+    let done = r#"{"document_id":"04DE5AD98A02647D83285A36021911C6","status":"done","billed_characters":12}"#;
+    let not_found = r#"{"message":"Document not found"}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![
+        mock_response(200, done),
+        mock_response(200, "Guten Morgen"),
+        mock_response(404, not_found),
+    ]);
+
+    let id = "04DE5AD98A02647D83285A36021911C6".to_string();
+    let key = "0CB0054F1C132C1625B392EEDE".to_string();
+    let out_file = env::temp_dir().join("deeprl-document-resume.txt");
+    let path = dl
+        .document_resume(id.clone(), key.clone(), Some(out_file))
+        .unwrap();
+    assert_eq!(std::fs::read_to_string(path).unwrap(), "Guten Morgen");
+
+    // expired handle
+    let res = dl.document_resume(id, key, None);
+    assert!(matches!(res, Err(Error::Document(msg)) if msg.contains("expired")));
+}
+
+#[test]
+fn document_status_poll() {
+    let translating = r#"{"document_id":"04DE5AD98A02647D83285A36021911C6","status":"translating","seconds_remaining":20}"#;
+    let done = r#"{"document_id":"04DE5AD98A02647D83285A36021911C6","status":"done","billed_characters":1337}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![
+        mock_response(200, translating),
+        mock_response(200, translating),
+        mock_response(200, done),
+    ]);
+
+    let doc = Document {
+        document_id: "04DE5AD98A02647D83285A36021911C6".to_string(),
+        document_key: "0CB0054F1C132C1625B392EEDE".to_string(),
+    };
+    let mut updates = vec![];
+    let status = dl
+        .document_status_poll(&doc, Duration::from_millis(10), |status| {
+            updates.push(status.seconds_remaining)
+        })
+        .unwrap();
+
+    assert_eq!(updates, [Some(20), Some(20), None]);
+    assert!(status.is_done());
+    assert_eq!(status.billed_characters, Some(1337));
+}
+
+#[test]
+fn document_status_serde() {
+    let json = r#"{"document_id":"04DE5AD98A02647D83285A36021911C6","status":"translating","seconds_remaining":20}"#;
+    let status: DocumentStatus = serde_json::from_str(json).unwrap();
+    assert_eq!(serde_json::to_string(&status).unwrap(), json);
+
+    for (state, name) in [
+        (DocState::Queued, "queued"),
+        (DocState::Translating, "translating"),
+        (DocState::Done, "done"),
+        (DocState::Error, "error"),
+    ] {
+        let s = serde_json::to_string(&state).unwrap();
+        assert_eq!(s, format!("\"{name}\""));
+        assert_eq!(serde_json::from_str::<DocState>(&s).unwrap(), state);
+    }
+}
+
+#[test]
+fn document_estimated_remaining() {
+    let mut status = DocumentStatus {
+        document_id: "04DE5AD98A02647D83285A36021911C6".to_string(),
+        status: DocState::Queued,
+        seconds_remaining: None,
+        billed_characters: None,
+        error_message: None,
+    };
+    assert!(status.is_queued());
+    assert!(status.estimated_remaining().is_none());
+
+    status.status = DocState::Translating;
+    status.seconds_remaining = Some(20);
+    assert!(!status.is_queued());
+    assert_eq!(status.estimated_remaining(), Some(Duration::from_secs(20)));
+
+    status.status = DocState::Error;
+    status.seconds_remaining = None;
+    assert!(status.is_error());
+    assert!(!status.is_done());
+}
+
+#[test]
+fn glossary_pairs() {
+    // get supported glossary language pairs
+    let dl = DeepL::new(KEY);
+
+    let result = dl.glossary_languages().unwrap();
+    let pairs = result.supported_languages;
+    assert!(!pairs.is_empty());
+    let first = &pairs[0];
+    assert!(!first.source_lang.is_empty());
+}
+
+#[test]
+fn glossaries() {
+    // list available glossaries
+    let dl = DeepL::new(KEY);
+
+    let result = dl.glossaries().unwrap();
+    let glossaries = result.glossaries;
+    if !glossaries.is_empty() {
+        let glos = &glossaries[0];
+        assert!(glos.entry_count > 0);
+    }
+}
+
+#[test]
+fn glossary_all() {
+    let dl = DeepL::new(KEY);
+
+    // create csv file with two glossary entries
+    let entry = "hello,ciao\n".to_string();
+    std::fs::write("glos.csv", entry).unwrap();
+
+    let entry = "goodbye,ciao\n".to_string();
+    let _wrote = std::fs::OpenOptions::new()
+        .append(true)
+        .open("glos.csv")
+        .unwrap()
+        .write(entry.as_bytes())
+        .unwrap();
+
+    // test create glossary
+    let name = "my_glossary".to_string();
+    let src = Language::EN;
+    let trg = Language::IT;
+    let entries = std::fs::read_to_string("glos.csv").unwrap();
+    let fmt = GlossaryEntriesFormat::Csv;
+
+    let glossary = dl.glossary_new(name, src, trg, entries, fmt).unwrap();
+    assert_eq!(glossary.entry_count, 2);
+    // TODO: `assert!(glossary.ready);` before attempting to use it
+
+    // test fetch entries
+    let glos_id = glossary.glossary_id;
+    let resp = dl.glossary_entries(&glos_id);
+    assert!(resp.is_ok());
+    let entries = resp.unwrap();
+    assert_eq!(entries.len(), 2);
+
+    // test translate with glossary
+    let opts = TextOptions::new(Language::IT)
+        .source_lang(Language::EN)
+        .preserve_formatting(true)
+        .glossary_id(glos_id.clone());
+
+    let text = vec!["goodbye".to_string()];
+    let result = dl.translate(opts, text).unwrap();
+    let translations = result.translations;
+    assert_eq!(translations[0].text, "ciao");
+
+    // test delete
+    let _: () = dl.glossary_delete(&glos_id).unwrap();
+    thread::sleep(Duration::from_secs(1));
 
     // deleted glossary id is 404
     let resp = dl.glossary_info(&glos_id);
     assert!(matches!(
         resp,
-        Err(Error::Server(code, ..)) if code == StatusCode::NOT_FOUND,
+        Err(Error::Server { status, .. }) if status == StatusCode::NOT_FOUND,
+    ));
+}
+
+#[test]
+fn glossary_replace_entries() {
+    let info = r#"{"glossary_id":"def3a26b-3e84-45b3-84ae-0c0aaf3525f7","ready":true,"name":"my_glossary","source_lang":"en","target_lang":"it","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1}"#;
+    let created = r#"{"glossary_id":"a1b2c3d4-3e84-45b3-84ae-0c0aaf3525f7","ready":true,"name":"my_glossary","source_lang":"en","target_lang":"it","creation_time":"2021-08-03T14:20:00.000Z","entry_count":2}"#;
+    let invalid = r#"{"message":"Invalid glossary entries provided"}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![
+        mock_response(200, info),
+        mock_response(204, ""),
+        mock_response(201, created),
+        mock_response(200, info),
+        mock_response(204, ""),
+        mock_response(400, invalid),
+    ]);
+
+    let entries = HashMap::from([
+        ("hello".to_string(), "ciao".to_string()),
+        ("goodbye".to_string(), "ciao".to_string()),
+    ]);
+    let glossary = dl
+        .glossary_replace_entries("def3a26b-3e84-45b3-84ae-0c0aaf3525f7", entries.clone())
+        .unwrap();
+    assert_eq!(glossary.name, "my_glossary");
+    assert_eq!(glossary.entry_count, 2);
+
+    // recreating fails after the original is gone
+    let res = dl.glossary_replace_entries("def3a26b-3e84-45b3-84ae-0c0aaf3525f7", entries);
+    assert!(matches!(res, Err(Error::Client(msg)) if msg.contains("was deleted")));
+
+    // invalid entries are rejected before anything is sent, leaving the glossary in place
+    let (url, requests) =
+        mock_server_requests(vec![mock_response(200, info), mock_response(204, "")]);
+    dl.url = url;
+    for (source, target) in [("hello", ""), ("hel\tlo", "ciao"), ("hello", "ci\nao")] {
+        let entries = HashMap::from([(source.to_string(), target.to_string())]);
+        let res = dl.glossary_replace_entries("def3a26b-3e84-45b3-84ae-0c0aaf3525f7", entries);
+        assert!(matches!(res, Err(Error::GlossaryEntry(..))));
+    }
+    let res = dl.glossary_replace_entries("def3a26b-3e84-45b3-84ae-0c0aaf3525f7", HashMap::new());
+    assert!(matches!(res, Err(Error::Client(_))));
+    assert!(requests.try_recv().is_err());
+}
+
+#[test]
+fn glossary_new_validation() {
+    let created = r#"{"glossary_id":"def3a26b-3e84-45b3-84ae-0c0aaf3525f7","ready":true,"name":"my_glossary","source_lang":"en","target_lang":"it","creation_time":"2021-08-03T14:16:18.329Z","entry_count":2}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(201, created)]);
+    let new = |entries: &str, fmt| {
+        dl.glossary_new(
+            "my_glossary".to_string(),
+            Language::EN,
+            Language::IT,
+            entries.to_string(),
+            fmt,
+        )
+    };
+
+    // wrong field count
+    let res = new("hello\tciao\n\ngoodbye ciao\n", GlossaryEntriesFormat::Tsv);
+    assert!(matches!(res, Err(Error::GlossaryEntry(3, line)) if line == "goodbye ciao"));
+
+    // duplicate source
+    let res = new(
+        "hello\tciao\r\nthanks\tgrazie\r\nhello\tsalve",
+        GlossaryEntriesFormat::Tsv,
+    );
+    assert!(matches!(res, Err(Error::GlossaryEntry(3, line)) if line == "hello\tsalve"));
+
+    // empty target
+    let res = new("hello,ciao\nthanks,", GlossaryEntriesFormat::Csv);
+    assert!(matches!(res, Err(Error::GlossaryEntry(2, _))));
+
+    // quoted csv fields may contain commas, and languages may be repeated
+    let csv = "\"hello, world\",\"ciao, mondo\"\nthanks,grazie,en,it";
+    assert!(new(csv, GlossaryEntriesFormat::Csv).is_ok());
+}
+
+#[test]
+fn glossary_v3_new() {
+    let created = r#"{"glossary_id":"def3a26b-3e84-45b3-84ae-0c0aaf3525f7","name":"greetings","dictionaries":[{"source_lang":"en","target_lang":"de","entry_count":1},{"source_lang":"en","target_lang":"it","entry_count":2}],"creation_time":"2024-08-03T14:16:18.329Z"}"#;
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) = mock_server_requests(vec![mock_response(201, created)]);
+    dl.url = url;
+
+    let fmt = GlossaryEntriesFormat::Tsv;
+    let dictionaries = vec![
+        GlossaryDictionary::new(Language::EN, Language::DE, "hello\tHallo".into(), fmt),
+        GlossaryDictionary::new(
+            Language::EN,
+            Language::IT,
+            "hello\tciao\nbye\tciao".into(),
+            fmt,
+        ),
+    ];
+    let glossary = dl
+        .glossary_v3_new("greetings".to_string(), dictionaries)
+        .unwrap();
+    assert_eq!(glossary.dictionaries.len(), 2);
+    assert_eq!(glossary.dictionaries[1].target_lang, "it");
+    assert_eq!(glossary.dictionaries[1].entry_count, 2);
+
+    let req = requests.recv().unwrap();
+    assert!(req.starts_with("POST /v3/glossaries "));
+    let body = &req[req.find("\r\n\r\n").unwrap() + 4..];
+    let json: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(json["name"], "greetings");
+    assert_eq!(json["dictionaries"][0]["target_lang"], "DE");
+    assert_eq!(json["dictionaries"][1]["entries_format"], "tsv");
+
+    // entries are checked per dictionary
+    let dictionaries = vec![GlossaryDictionary::new(
+        Language::EN,
+        Language::DE,
+        "hello".into(),
+        fmt,
+    )];
+    let res = dl.glossary_v3_new("bad".to_string(), dictionaries);
+    assert!(matches!(res, Err(Error::GlossaryEntry(1, _))));
+}
+
+#[test]
+fn glossary_entries_stream() {
+    // the last line has no trailing newline
+    let tsv = "hello\tciao\r\n\nbye\tciao ciao\none\ttwo\tthree\nmissing\nthanks\tgrazie";
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(200, tsv)]);
+
+    let entries: Vec<_> = dl
+        .glossary_entries_stream("def3a26b-3e84-45b3-84ae-0c0aaf3525f7")
+        .unwrap()
+        .collect();
+    assert_eq!(entries.len(), 5);
+
+    assert_eq!(
+        entries[0].as_ref().unwrap(),
+        &("hello".to_string(), "ciao".to_string())
+    );
+    assert_eq!(entries[1].as_ref().unwrap().1, "ciao ciao");
+    assert!(matches!(&entries[2], Err(Error::GlossaryEntry(4, line)) if line == "one\ttwo\tthree"));
+    assert!(matches!(&entries[3], Err(Error::GlossaryEntry(5, line)) if line == "missing"));
+    assert_eq!(entries[4].as_ref().unwrap().0, "thanks");
+}
+
+#[test]
+fn glossary_lookup() {
+    let info = r#"{"glossary_id":"def3a26b-3e84-45b3-84ae-0c0aaf3525f7","ready":true,"name":"my_glossary","source_lang":"en","target_lang":"it","creation_time":"2021-08-03T14:16:18.329Z","entry_count":2}"#;
+    let id = "def3a26b-3e84-45b3-84ae-0c0aaf3525f7";
+    let mut dl = DeepL::new(KEY);
+    // entries are fetched once, later lookups only fetch the glossary info
+    dl.url = mock_server(vec![
+        mock_response(200, info),
+        mock_response(200, "hello\tciao\nbye\tciao ciao"),
+        mock_response(200, info),
+        mock_response(200, info),
+        mock_response(204, ""),
+    ]);
+
+    assert_eq!(
+        dl.glossary_lookup(id, "hello").unwrap().as_deref(),
+        Some("ciao")
+    );
+    assert_eq!(
+        dl.glossary_lookup(id, "bye").unwrap().as_deref(),
+        Some("ciao ciao")
+    );
+    assert_eq!(dl.glossary_lookup(id, "thanks").unwrap(), None);
+
+    // deleting the glossary evicts its entries
+    assert!(dl.glossary_cache.lock().unwrap().contains_key(id));
+    dl.glossary_delete(id).unwrap();
+    assert!(dl.glossary_cache.lock().unwrap().is_empty());
+}
+
+#[test]
+fn glossaries_new_bulk() {
+    let created = |id: &str, target: &str| {
+        format!(
+            r#"{{"glossary_id":"{id}","ready":true,"name":"project","source_lang":"en","target_lang":"{target}","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1}}"#
+        )
+    };
+    let spec = |target| GlossarySpec {
+        name: "project".to_string(),
+        source_lang: Language::EN,
+        target_lang: target,
+        entries: "hello\tciao".to_string(),
+        fmt: GlossaryEntriesFormat::Tsv,
+    };
+    let specs = vec![spec(Language::IT), spec(Language::DE), spec(Language::FR)];
+
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![
+        mock_response(201, &created("id-it", "it")),
+        mock_response(201, &created("id-de", "de")),
+        mock_response(201, &created("id-fr", "fr")),
+    ]);
+    let glossaries = dl.glossaries_new_bulk(specs.clone()).unwrap();
+    let ids: Vec<&str> = glossaries.iter().map(|g| g.glossary_id.as_str()).collect();
+    assert_eq!(ids, ["id-it", "id-de", "id-fr"]);
+
+    // the glossaries created before the failure are deleted
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) = mock_server_requests(vec![
+        mock_response(201, &created("id-it", "it")),
+        mock_response(201, &created("id-de", "de")),
+        mock_response(400, r#"{"message":"Invalid glossary entries"}"#),
+        mock_response(204, ""),
+        mock_response(204, ""),
+    ]);
+    dl.url = url;
+    let res = dl.glossaries_new_bulk(specs);
+    assert!(matches!(
+        res,
+        Err(Error::Server {
+            status: StatusCode::BAD_REQUEST,
+            ..
+        })
+    ));
+
+    let requests: Vec<String> = requests.iter().collect();
+    assert!(requests[3].starts_with("DELETE /v2/glossaries/id-it "));
+    assert!(requests[4].starts_with("DELETE /v2/glossaries/id-de "));
+}
+
+#[test]
+fn glossary_new_scoped() {
+    let created = r#"{"glossary_id":"def3a26b-3e84-45b3-84ae-0c0aaf3525f7","ready":true,"name":"my_glossary","source_lang":"en","target_lang":"it","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1}"#;
+    fn new_scoped(dl: &DeepL) -> ScopedGlossary<'_> {
+        dl.glossary_new_scoped(
+            "my_glossary".to_string(),
+            Language::EN,
+            Language::IT,
+            "hello\tciao".to_string(),
+            GlossaryEntriesFormat::Tsv,
+        )
+        .unwrap()
+    }
+
+    // deleted on drop
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) =
+        mock_server_requests(vec![mock_response(201, created), mock_response(204, "")]);
+    dl.url = url;
+    let glossary = new_scoped(&dl);
+    assert_eq!(glossary.entry_count, 1);
+    drop(glossary);
+
+    assert!(requests.recv().unwrap().starts_with("POST /v2/glossaries "));
+    assert!(requests
+        .recv()
+        .unwrap()
+        .starts_with("DELETE /v2/glossaries/def3a26b-3e84-45b3-84ae-0c0aaf3525f7 "));
+
+    // kept after into_inner, the server only expects the one request
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) = mock_server_requests(vec![mock_response(201, created)]);
+    dl.url = url;
+    let glossary = new_scoped(&dl).into_inner();
+    assert_eq!(glossary.name, "my_glossary");
+
+    assert!(requests.recv().is_ok());
+    assert!(requests.recv().is_err());
+}
+
+#[test]
+fn glossary_new_checked() {
+    let pairs = r#"{"supported_languages":[{"source_lang":"en","target_lang":"de"},{"source_lang":"en","target_lang":"it"}]}"#;
+    let created = r#"{"glossary_id":"def3a26b-3e84-45b3-84ae-0c0aaf3525f7","ready":true,"name":"my_glossary","source_lang":"en","target_lang":"it","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![
+        mock_response(200, pairs),
+        mock_response(201, created),
+        mock_response(200, pairs),
+    ]);
+
+    let glos = dl
+        .glossary_new_checked(
+            "my_glossary".to_string(),
+            Language::ENGB,
+            Language::IT,
+            "hello\tciao".to_string(),
+            GlossaryEntriesFormat::Tsv,
+        )
+        .unwrap();
+    assert_eq!(glos.entry_count, 1);
+
+    let res = dl.glossary_new_checked(
+        "my_glossary".to_string(),
+        Language::EN,
+        Language::JA,
+        "hello\tkon'nichiwa".to_string(),
+        GlossaryEntriesFormat::Tsv,
+    );
+    assert!(
+        matches!(res, Err(Error::Client(msg)) if msg == "unsupported glossary language pair EN->JA")
+    );
+}
+
+#[test]
+fn glossary_new_auto() {
+    use GlossaryEntriesFormat::*;
+
+    assert!(matches!(
+        GlossaryEntriesFormat::detect("hello\tciao"),
+        Some(Tsv)
+    ));
+    assert!(matches!(
+        GlossaryEntriesFormat::detect("\n\"a, b\"\tc"),
+        Some(Tsv)
+    ));
+    assert!(matches!(
+        GlossaryEntriesFormat::detect("hello,ciao\r\n"),
+        Some(Csv)
+    ));
+    assert!(GlossaryEntriesFormat::detect("hello ciao").is_none());
+    assert!(GlossaryEntriesFormat::detect("").is_none());
+
+    let created = r#"{"glossary_id":"def3a26b-3e84-45b3-84ae-0c0aaf3525f7","ready":true,"name":"my_glossary","source_lang":"en","target_lang":"it","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1}"#;
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) = mock_server_requests(vec![mock_response(201, created)]);
+    dl.url = url;
+
+    let glos = dl
+        .glossary_new_auto(
+            "my_glossary".to_string(),
+            Language::EN,
+            Language::IT,
+            "hello,ciao".to_string(),
+        )
+        .unwrap();
+    assert_eq!(glos.entry_count, 1);
+    assert!(requests.recv().unwrap().contains("entries_format=csv"));
+
+    // nothing is sent if the format is ambiguous
+    let res = dl.glossary_new_auto(
+        "my_glossary".to_string(),
+        Language::EN,
+        Language::IT,
+        "hello ciao".to_string(),
+    );
+    assert!(matches!(res, Err(Error::Client(_))));
+}
+
+#[test]
+fn glossary_exists() {
+    let created = r#"{"glossary_id":"def3a26b-3e84-45b3-84ae-0c0aaf3525f7","ready":true,"name":"my_glossary","source_lang":"en","target_lang":"it","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1}"#;
+    let not_found = r#"{"message":"Glossary not found"}"#;
+    let forbidden = r#"{"message":"Wrong endpoint"}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![
+        mock_response(201, created),
+        mock_response(200, created),
+        mock_response(204, ""),
+        mock_response(404, not_found),
+        mock_response(404, not_found),
+        mock_response(403, forbidden),
+    ]);
+
+    let glos = dl
+        .glossary_new(
+            "my_glossary".to_string(),
+            Language::EN,
+            Language::IT,
+            "hello\tciao".to_string(),
+            GlossaryEntriesFormat::Tsv,
+        )
+        .unwrap();
+    let id = glos.glossary_id;
+
+    assert!(dl.glossary_exists(&id).unwrap());
+    dl.glossary_delete(&id).unwrap();
+    assert!(!dl.glossary_exists(&id).unwrap());
+
+    // deleting a missing glossary is an error
+    let res = dl.glossary_delete(&id);
+    assert!(matches!(
+        res,
+        Err(Error::Server {
+            status: StatusCode::NOT_FOUND,
+            ..
+        })
+    ));
+
+    // other errors are propagated
+    let res = dl.glossary_exists(&id);
+    assert!(matches!(
+        res,
+        Err(Error::Server {
+            status: StatusCode::FORBIDDEN,
+            ..
+        })
     ));
 }
 
+#[test]
+fn find_glossaries_by_name() {
+    let a = r#"{"glossary_id":"a","ready":true,"name":"shared","source_lang":"en","target_lang":"de","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1}"#;
+    let b = r#"{"glossary_id":"b","ready":true,"name":"shared","source_lang":"en","target_lang":"fr","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1}"#;
+    let list = r#"{"glossaries":[
+        {"glossary_id":"a","ready":true,"name":"shared","source_lang":"en","target_lang":"de","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1},
+        {"glossary_id":"b","ready":true,"name":"shared","source_lang":"en","target_lang":"fr","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1},
+        {"glossary_id":"c","ready":true,"name":"Shared","source_lang":"en","target_lang":"it","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1},
+        {"glossary_id":"d","ready":true,"name":"other","source_lang":"en","target_lang":"de","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1}
+    ]}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![
+        mock_response(201, a),
+        mock_response(201, b),
+        mock_response(200, list),
+        mock_response(200, list),
+    ]);
+
+    for trg in [Language::DE, Language::FR] {
+        let entries = "hello\tworld".to_string();
+        let fmt = GlossaryEntriesFormat::Tsv;
+        dl.glossary_new("shared".into(), Language::EN, trg, entries, fmt)
+            .unwrap();
+    }
+
+    let ids = |glossaries: Vec<Glossary>| -> Vec<String> {
+        glossaries.into_iter().map(|g| g.glossary_id).collect()
+    };
+
+    let res = dl.find_glossaries_by_name("shared").unwrap();
+    assert_eq!(ids(res), ["a", "b"]);
+
+    let res = dl.find_glossaries_by_name_ignore_case("SHARED").unwrap();
+    assert_eq!(ids(res), ["a", "b", "c"]);
+}
+
+#[test]
+#[allow(deprecated)]
+fn glossaries_for() {
+    let body = r#"{"glossaries":[
+        {"glossary_id":"a","ready":true,"name":"en-de","source_lang":"en","target_lang":"de","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1},
+        {"glossary_id":"b","ready":true,"name":"de-en","source_lang":"de","target_lang":"en","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1},
+        {"glossary_id":"c","ready":true,"name":"en-fr","source_lang":"en","target_lang":"fr","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1},
+        {"glossary_id":"d","ready":true,"name":"en-de 2","source_lang":"en","target_lang":"de","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1},
+        {"glossary_id":"e","ready":true,"name":"unknown","source_lang":"en","target_lang":"xx","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1}
+    ]}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(200, body), mock_response(200, body)]);
+
+    let ids = |glossaries: Vec<Glossary>| -> Vec<String> {
+        glossaries.into_iter().map(|g| g.glossary_id).collect()
+    };
+
+    let res = dl.glossaries_for(Language::EN, Language::DE).unwrap();
+    assert_eq!(ids(res), ["a", "d"]);
+
+    // regional variants match their base language
+    let res = dl.glossaries_for(Language::DE, Language::ENGB).unwrap();
+    assert_eq!(ids(res), ["b"]);
+}
+
+#[test]
+fn glossaries_for_pair() {
+    let body = r#"{"glossaries":[
+        {"glossary_id":"a","ready":true,"name":"en-de","source_lang":"en","target_lang":"de","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1},
+        {"glossary_id":"b","ready":true,"name":"de-en","source_lang":"de","target_lang":"en","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1},
+        {"glossary_id":"c","ready":true,"name":"en-fr","source_lang":"en","target_lang":"fr","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1},
+        {"glossary_id":"d","ready":true,"name":"en-de 2","source_lang":"EN","target_lang":"De","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1},
+        {"glossary_id":"e","ready":true,"name":"unknown","source_lang":"en","target_lang":"xx","creation_time":"2021-08-03T14:16:18.329Z","entry_count":1}
+    ]}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(200, body), mock_response(200, body)]);
+
+    let ids = |glossaries: Vec<Glossary>| -> Vec<String> {
+        glossaries.into_iter().map(|g| g.glossary_id).collect()
+    };
+
+    // lowercase codes as sent by DeepL match, as do codes in any other case
+    let res = dl.glossaries_for_pair(Language::EN, Language::DE).unwrap();
+    assert_eq!(ids(res), ["a", "d"]);
+
+    // regional variants match their base language
+    let res = dl
+        .glossaries_for_pair(Language::DE, Language::ENGB)
+        .unwrap();
+    assert_eq!(ids(res), ["b"]);
+}
+
+#[test]
+fn glossary_entries_checked() {
+    let tsv = "hello\tciao\ngoodbye ciao\n";
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(200, tsv), mock_response(200, tsv)]);
+
+    // the lenient version skips the malformed line
+    let entries = dl.glossary_entries("def3a26b").unwrap();
+    assert_eq!(entries.len(), 1);
+
+    let res = dl.glossary_entries_checked("def3a26b");
+    assert!(matches!(res, Err(Error::GlossaryEntry(2, line)) if line == "goodbye ciao"));
+}
+
+#[test]
+fn glossary_tbx() {
+    let tbx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<martif type="TBX" xml:lang="en">
+  <text><body>
+    <termEntry id="1">
+      <langSet xml:lang="en-US"><tig><term>hello</term></tig></langSet>
+      <langSet xml:lang="it"><tig><term>ciao</term></tig></langSet>
+      <langSet xml:lang="de"><tig><term>hallo</term></tig></langSet>
+    </termEntry>
+    <termEntry id="2">
+      <langSet xml:lang="it"><tig><term>grazie &amp; prego</term></tig></langSet>
+      <langSet xml:lang="en"><tig><termNote type="partOfSpeech">noun</termNote><term>thanks &amp; welcome</term></tig></langSet>
+    </termEntry>
+    <termEntry id="3">
+      <langSet xml:lang="en"><tig><term>goodbye</term></tig></langSet>
+    </termEntry>
+  </body></text>
+</martif>"#;
+    let created = r#"{"glossary_id":"def3a26b-3e84-45b3-84ae-0c0aaf3525f7","ready":true,"name":"tbx","source_lang":"en","target_lang":"it","creation_time":"2021-08-03T14:16:18.329Z","entry_count":2}"#;
+    let mut dl = DeepL::new(KEY);
+    let (url, requests) = mock_server_requests(vec![mock_response(201, created)]);
+    dl.url = url;
+
+    let fmt = GlossaryEntriesFormat::Tbx;
+    let glossary = dl
+        .glossary_new("tbx".into(), Language::EN, Language::IT, tbx.into(), fmt)
+        .unwrap();
+    assert_eq!(glossary.entry_count, 2);
+
+    // sent as tsv
+    let req = requests.recv().unwrap();
+    assert!(req.contains("entries=hello%09ciao%0Athanks+%26+welcome%09grazie+%26+prego%0A"));
+    assert!(req.contains("entries_format=tsv"));
+
+    // no entries for the pair
+    let res = dl.glossary_new("tbx".into(), Language::EN, Language::FR, tbx.into(), fmt);
+    assert!(matches!(res, Err(Error::Client(_))));
+}
+
 #[test]
 fn test_error() {
     let dl = DeepL::new(KEY);
@@ -284,6 +2576,125 @@ fn test_error() {
     assert!(res.is_err());
 }
 
+#[test]
+fn server_error_code() {
+    let body =
+        r#"{"message":"Value for 'source_lang' not supported.","code":"invalid_source_lang"}"#;
+    let err: ServerError = serde_json::from_str(body).unwrap();
+    assert_eq!(err.code.as_deref(), Some("invalid_source_lang"));
+
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(400, body)]);
+    let res = dl.translate_string("good morning", Language::DE);
+    let err = res.unwrap_err();
+    assert!(matches!(
+        &err,
+        Error::Server { status, code: Some(code), .. }
+            if *status == StatusCode::BAD_REQUEST && code == "invalid_source_lang",
+    ));
+    assert_eq!(err.code(), Some("invalid_source_lang"));
+    assert_eq!(
+        err.to_string(),
+        "400 Bad Request: Value for 'source_lang' not supported. (invalid_source_lang)"
+    );
+
+    // the code is optional
+    let err: ServerError = serde_json::from_str(r#"{"message":"Bad request"}"#).unwrap();
+    assert!(err.code.is_none());
+}
+
+#[test]
+fn server_error_detail() {
+    let body = r#"{"message":"Invalid glossary entries provided","detail":"Key with the index 1 (starting at position 13) duplicates key with the index 0 (starting at position 0)"}"#;
+    let err: ServerError = serde_json::from_str(body).unwrap();
+    assert!(err.detail.is_some());
+
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(400, body)]);
+    let err = dl
+        .glossary_info("def3a26b-3e84-45b3-84ae-0c0aaf3525f7")
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "400 Bad Request: Invalid glossary entries provided: Key with the index 1 (starting at position 13) duplicates key with the index 0 (starting at position 0)"
+    );
+}
+
+#[test]
+fn rate_limited() {
+    let resp = |retry_after: &str| {
+        let body = r#"{"message":"Too many requests"}"#;
+        mock_response(429, body).replacen(
+            "\r\n\r\n",
+            &format!("\r\nRetry-After: {retry_after}\r\n\r\n"),
+            1,
+        )
+    };
+    let body = r#"{"translations":[{"detected_source_language":"EN","text":"Hallo"}]}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![
+        resp("5"),
+        resp("Wed, 21 Oct 2015 07:28:00 GMT"),
+        resp("0"),
+        mock_response(200, body),
+    ]);
+
+    let res = dl.translate_string("hello", Language::DE);
+    assert!(matches!(res, Err(Error::RateLimited(Some(d))) if d == Duration::from_secs(5)));
+    let res = dl.translate_string("hello", Language::DE);
+    assert!(matches!(res, Err(Error::RateLimited(None))));
+    assert!(Error::RateLimited(None).is_retryable());
+
+    // retries wait as long as the server asks
+    dl.set_max_retries(1);
+    assert_eq!(dl.translate_string("hello", Language::DE).unwrap(), "Hallo");
+}
+
+#[test]
+fn error_is_retryable() {
+    let server = |code: u16| Error::Server {
+        status: StatusCode::from_u16(code).unwrap(),
+        message: String::new(),
+        code: None,
+    };
+    for code in [429, 500, 502, 503, 529] {
+        assert!(server(code).is_retryable());
+    }
+    for code in [400, 403, 404, 456] {
+        assert!(!server(code).is_retryable());
+    }
+    assert!(!Error::InvalidLanguage.is_retryable());
+    assert!(!Error::Client("empty text parameter".to_string()).is_retryable());
+
+    // nothing listens on port 1
+    let err = reqwest::blocking::get("http://127.0.0.1:1").unwrap_err();
+    assert!(Error::Reqwest(err).is_retryable());
+}
+
+#[test]
+fn translate_validate_languages() {
+    let dl = DeepL::new(KEY);
+
+    // rejected before sending, so no server is needed
+    let opt = TextOptions::new(Language::PT).source_lang(Language::ENGB);
+    let res = dl.translate(opt, vec!["good morning".to_string()]);
+    assert!(
+        matches!(res, Err(Error::Client(msg)) if msg == "PT cannot be used as a target language")
+    );
+
+    // opt out
+    let body = r#"{"translations":[{"detected_source_language":"EN","text":"Bom dia"}]}"#;
+    let mut dl = DeepL::new(KEY);
+    dl.url = mock_server(vec![mock_response(200, body); 2]);
+
+    let opt = TextOptions::new(Language::PT).validate_languages(false);
+    assert!(dl.translate(opt, vec!["good morning".to_string()]).is_ok());
+
+    // a target-only source is sent as its base language rather than rejected
+    let opt = TextOptions::new(Language::PTBR).source_lang(Language::ENGB);
+    assert!(dl.translate(opt, vec!["good morning".to_string()]).is_ok());
+}
+
 // Doc tests
 #[test]
 fn doc_text_options() {
@@ -1,10 +1,13 @@
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
 
 use super::{Error, Result};
+use crate::glos::xml_unescape;
 use crate::{builder, DeepL, Language};
 
 /// Sets whether the translation engine should first split the input into sentences
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub enum SplitSentences {
     /// No splitting
     None,
@@ -15,7 +18,7 @@ pub enum SplitSentences {
 }
 
 /// Sets whether the translation engine should lean towards formal or informal language
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub enum Formality {
     /// Default formality
     Default,
@@ -30,7 +33,7 @@ pub enum Formality {
 }
 
 /// Sets which kind of tags should be handled
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub enum TagHandling {
     /// Enable XML tag handling
     Xml,
@@ -38,6 +41,42 @@ pub enum TagHandling {
     Html,
 }
 
+/// Sets which kind of model should be used for translation
+#[derive(Copy, Clone, Debug)]
+pub enum ModelType {
+    /// Use the highest quality model available for the language pair
+    QualityOptimized,
+    /// Use a model optimized for low latency
+    LatencyOptimized,
+    /// Use the highest quality model if available for the language pair, else a
+    /// latency optimized model
+    PreferQualityOptimized,
+}
+
+/// Result of a lenient translation, see [`DeepL::translate_lenient`]
+#[derive(Debug)]
+pub struct LenientTranslateResult {
+    /// Translations that could be read from the response
+    pub translations: Vec<Translation>,
+    /// Number of malformed translations that were skipped
+    pub skipped: usize,
+}
+
+/// A translation in a response that is read leniently, such that one malformed
+/// translation doesn't fail the whole response
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MaybeTranslation {
+    Valid(Translation),
+    Malformed(serde::de::IgnoredAny),
+}
+
+/// Translation response read leniently
+#[derive(Deserialize)]
+struct LenientResponse {
+    translations: Vec<MaybeTranslation>,
+}
+
 /// An individual translation
 #[derive(Debug, Deserialize)]
 pub struct Translation {
@@ -45,6 +84,9 @@ pub struct Translation {
     pub detected_source_language: String,
     /// Translated text
     pub text: String,
+    /// Original input text, attached by the client when known
+    #[serde(default)]
+    pub source_text: Option<String>,
 }
 
 /// Translation result
@@ -52,6 +94,31 @@ pub struct Translation {
 pub struct TranslateTextResult {
     /// List of translations
     pub translations: Vec<Translation>,
+    /// The kind of model used for translation, included if `model_type` was set
+    #[serde(default)]
+    pub model_type_used: Option<String>,
+}
+
+impl Translation {
+    /// The detected source language as a [`Language`], or `None` if the code is not
+    /// known to this crate, e.g. a language newly supported by DeepL
+    pub fn detected_language(&self) -> Option<Language> {
+        self.detected_source_language.parse().ok()
+    }
+}
+
+impl AsRef<str> for Translation {
+    fn as_ref(&self) -> &str {
+        self.text.as_str()
+    }
+}
+
+impl std::ops::Deref for Translation {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.text.as_str()
+    }
 }
 
 impl AsRef<str> for SplitSentences {
@@ -76,22 +143,114 @@ impl AsRef<str> for Formality {
     }
 }
 
+impl AsRef<str> for ModelType {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::QualityOptimized => "quality_optimized",
+            Self::LatencyOptimized => "latency_optimized",
+            Self::PreferQualityOptimized => "prefer_quality_optimized",
+        }
+    }
+}
+
+impl std::str::FromStr for SplitSentences {
+    type Err = Error;
+
+    /// # Errors
+    ///
+    /// If `s` is not one of "0", "1", or "nonewlines"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ss = match s {
+            "0" => Self::None,
+            "1" => Self::Default,
+            "nonewlines" => Self::NoNewlines,
+            _ => return Err(Error::Client(format!("invalid split_sentences: {s}"))),
+        };
+
+        Ok(ss)
+    }
+}
+
 impl std::str::FromStr for Formality {
     type Err = Error;
 
+    /// # Errors
+    ///
+    /// If `s` is not one of "default", "more", "less", "prefer_more", or "prefer_less"
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let fm = match s {
+            "default" => Self::Default,
             "more" => Self::More,
             "less" => Self::Less,
             "prefer_more" => Self::PreferMore,
             "prefer_less" => Self::PreferLess,
-            _ => Self::Default,
+            _ => return Err(Error::Client(format!("invalid formality: {s}"))),
         };
 
         Ok(fm)
     }
 }
 
+impl std::str::FromStr for TagHandling {
+    type Err = Error;
+
+    /// # Errors
+    ///
+    /// If `s` is not one of "xml" or "html"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let th = match s {
+            "xml" => Self::Xml,
+            "html" => Self::Html,
+            _ => return Err(Error::Client(format!("invalid tag_handling: {s}"))),
+        };
+
+        Ok(th)
+    }
+}
+
+impl std::str::FromStr for ModelType {
+    type Err = Error;
+
+    /// # Errors
+    ///
+    /// If `s` is not one of "quality_optimized", "latency_optimized", or
+    /// "prefer_quality_optimized"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mt = match s {
+            "quality_optimized" => Self::QualityOptimized,
+            "latency_optimized" => Self::LatencyOptimized,
+            "prefer_quality_optimized" => Self::PreferQualityOptimized,
+            _ => return Err(Error::Client(format!("invalid model_type: {s}"))),
+        };
+
+        Ok(mt)
+    }
+}
+
+impl std::fmt::Display for SplitSentences {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_ref())
+    }
+}
+
+impl std::fmt::Display for Formality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_ref())
+    }
+}
+
+impl std::fmt::Display for TagHandling {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_ref())
+    }
+}
+
+impl std::fmt::Display for ModelType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_ref())
+    }
+}
+
 impl AsRef<str> for TagHandling {
     fn as_ref(&self) -> &str {
         match self {
@@ -101,6 +260,59 @@ impl AsRef<str> for TagHandling {
     }
 }
 
+impl Formality {
+    /// The formality to send for `target`, or `None` to omit the parameter.
+    ///
+    /// DeepL rejects `More` and `Less` for targets that don't support formality, so they
+    /// are omitted, whereas the `Prefer*` variants are accepted and sent as is.
+    pub(crate) fn for_target(self, target: Language) -> Option<Formality> {
+        match self {
+            Self::More | Self::Less if !target.supports_formality() => None,
+            fm => Some(fm),
+        }
+    }
+}
+
+impl TagHandling {
+    /// Guesses the kind of tags in `text`, if any, to help choose a `tag_handling` mode.
+    ///
+    /// This is a heuristic which only looks for an XML declaration (`<?xml`) or a few
+    /// common HTML elements such as `<html`, `<body`, `<p>`, or `<div`. Returns `None` if
+    /// neither is found, including for text containing other kinds of tags.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use deeprl::TagHandling;
+    /// assert!(matches!(TagHandling::guess("<p>Hello</p>"), Some(TagHandling::Html)));
+    /// assert!(TagHandling::guess("1 < 2").is_none());
+    /// ```
+    pub fn guess(text: &str) -> Option<TagHandling> {
+        let text = text.trim_start().to_ascii_lowercase();
+
+        if text.starts_with("<?xml") {
+            return Some(Self::Xml);
+        }
+
+        let html = HTML_MARKERS.iter().any(|marker| text.contains(marker));
+        html.then_some(Self::Html)
+    }
+}
+
+/// Start tags that mark text as HTML, see [`TagHandling::guess`]
+const HTML_MARKERS: [&str; 10] = [
+    "<!doctype html",
+    "<html",
+    "<head>",
+    "<body",
+    "<p>",
+    "<p ",
+    "<div",
+    "<span",
+    "<br",
+    "<a href",
+];
+
 // TextOptions builder
 builder! {
     Text {
@@ -118,21 +330,235 @@ builder! {
             outline_detection: bool,
             splitting_tags: String,
             ignore_tags: String,
+            verify_tags: bool,
+            validate_languages: bool,
+            model_type: ModelType,
+            match_source_case: bool,
+            normalize_void_tags: bool,
+        };
+        @list{
+            text: String,
+        };
+        @private{
+            protected_terms: Vec<String>,
         };
     }
 }
 
 impl TextOptions {
+    /// Whether the formality preference can take effect for the target language.
+    ///
+    /// DeepL silently falls back to the default formality when `PreferMore` or
+    /// `PreferLess` is used with a target that doesn't support formality, see
+    /// [`Language::supports_formality`]. This is always `true` if no formality, or
+    /// [`Formality::Default`], is set. No request is made to the server.
+    pub fn formality_applied(&self) -> bool {
+        match self.formality {
+            None | Some(Formality::Default) => true,
+            Some(_) => self.target_lang.supports_formality(),
+        }
+    }
+
+    /// Protects each of `terms` from translation, e.g. product names or code identifiers,
+    /// such that they appear verbatim in the translation.
+    ///
+    /// The text is sent with XML tag handling: it is escaped and each occurrence of a term
+    /// is wrapped in an ignored `<x>` tag before sending, which is undone in the
+    /// translation. Terms are matched literally and case-sensitively. Where terms overlap,
+    /// the longest match starting first wins. Text is therefore assumed to be plain, not
+    /// XML, and translating fails with [`Error::Client`] if `tag_handling` is set as well.
+    #[must_use]
+    pub fn protect_terms(mut self, terms: &[&str]) -> Self {
+        let terms = terms
+            .iter()
+            .filter(|t| !t.is_empty())
+            .map(|t| t.to_string())
+            .collect();
+        self.protected_terms = Some(terms);
+        self
+    }
+
+    /// Number of characters the text set on the options is expected to be billed for.
+    ///
+    /// DeepL counts characters by Unicode code points, not bytes, so e.g. `"Grüße"` counts
+    /// as 5. Only the `text` parameter is billed, not other options. Text passed directly to
+    /// [`DeepL::translate`] is not included.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::*;
+    /// # let dl = DeepL::new(&std::env::var("DEEPL_API_KEY").unwrap());
+    /// let opt = TextOptions::new(Language::DE).texts(["good morning", "good night"]);
+    ///
+    /// let usage = dl.usage().unwrap();
+    /// if opt.estimated_characters() <= usage.characters_remaining() {
+    ///     dl.translate(opt, vec![]).unwrap();
+    /// }
+    /// ```
+    pub fn estimated_characters(&self) -> u64 {
+        count_characters(self.text.as_deref().unwrap_or_default())
+    }
+
+    /// Whether any terms are protected from translation
+    fn protects_terms(&self) -> bool {
+        self.protected_terms.as_ref().is_some_and(|t| !t.is_empty())
+    }
+
+    /// The tag handling sent to DeepL, which is XML when terms are protected
+    fn effective_tag_handling(&self) -> Option<TagHandling> {
+        if self.protected_terms.is_some() {
+            Some(TagHandling::Xml)
+        } else {
+            self.tag_handling
+        }
+    }
+
+    /// Whether void elements are sent as self-closing tags
+    fn normalizes_void_tags(&self) -> bool {
+        self.normalize_void_tags == Some(true)
+            && matches!(self.tag_handling, Some(TagHandling::Html))
+    }
+
+    /// Rewrites `text` as it is sent to DeepL, if protected terms or void tag normalization
+    /// require it. Protected terms take priority.
+    fn prepare_text(&self, text: &[String]) -> Option<Vec<String>> {
+        if self.protects_terms() {
+            let terms = self.protected_terms.as_deref().unwrap_or_default();
+            Some(text.iter().map(|t| protect(t, terms)).collect())
+        } else if self.normalizes_void_tags() {
+            Some(text.iter().map(|t| rewrite_void_tags(t, true)).collect())
+        } else {
+            None
+        }
+    }
+
+    /// The form-encoded request body [`DeepL::translate`] would send for these options and
+    /// the text set on them, e.g. for logging. Nothing is sent.
+    ///
+    /// Note: the body contains the text as well as the glossary id, if set, which may need to
+    /// be redacted before logging.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use deeprl::*;
+    /// let opt = TextOptions::new(Language::DE)
+    ///     .formality(Formality::Less)
+    ///     .texts(["good morning"]);
+    ///
+    /// assert_eq!(
+    ///     opt.to_request_string(),
+    ///     "target_lang=DE&formality=less&text=good+morning"
+    /// );
+    /// ```
+    pub fn to_request_string(&self) -> String {
+        let text = self.text.as_deref().unwrap_or_default();
+        let prepared = self.prepare_text(text);
+
+        // encoded the same way as `RequestBuilder::form`
+        let mut url = reqwest::Url::parse("http://localhost").unwrap();
+        url.query_pairs_mut()
+            .extend_pairs(self.to_params(prepared.as_deref().unwrap_or(text)));
+        url.query().unwrap_or_default().to_string()
+    }
+
+    /// Sets the text to translate from any iterable of string-likes, e.g. `["a", "b"]`.
+    ///
+    /// Text set on the options is sent ahead of any text passed directly to
+    /// [`DeepL::translate`].
+    #[must_use]
+    pub fn texts<I, S>(self, texts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.text(texts.into_iter().map(Into::into).collect())
+    }
+
+    /// Sets `splitting_tags` from any iterable of tag names, e.g. `["p", "div"]`.
+    #[must_use]
+    pub fn splitting_tags_iter<I, S>(self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.splitting_tags(join_tags(tags))
+    }
+
+    /// Sets `non_splitting_tags` from any iterable of tag names, e.g. `["b", "i"]`.
+    #[must_use]
+    pub fn non_splitting_tags_iter<I, S>(self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.non_splitting_tags(join_tags(tags))
+    }
+
+    /// Sets `ignore_tags` from any iterable of tag names, e.g. `["code", "pre"]`.
+    #[must_use]
+    pub fn ignore_tags_iter<I, S>(self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.ignore_tags(join_tags(tags))
+    }
+
+    /// Checks that tag options are only set along with `tag_handling`, and that
+    /// `tag_handling` isn't combined with protected terms, then validates the target
+    /// language unless disabled with `validate_languages(false)`
+    fn validate(&self) -> Result<()> {
+        if self.protected_terms.is_some() && self.tag_handling.is_some() {
+            return Err(Error::Client(
+                "tag_handling cannot be set along with protect_terms, which uses XML tag handling"
+                    .to_string(),
+            ));
+        }
+        if self.effective_tag_handling().is_none() {
+            let tag_opts = [
+                ("splitting_tags", self.splitting_tags.is_some()),
+                ("non_splitting_tags", self.non_splitting_tags.is_some()),
+                ("ignore_tags", self.ignore_tags.is_some()),
+                ("outline_detection", self.outline_detection.is_some()),
+            ];
+            if let Some((name, _)) = tag_opts.iter().find(|(_, set)| *set) {
+                return Err(Error::Client(format!(
+                    "{name} has no effect without tag_handling, set it with `.tag_handling(...)`"
+                )));
+            }
+        }
+
+        if self.validate_languages == Some(false) {
+            return Ok(());
+        }
+        validate_target_lang(self.target_lang)
+    }
+
+    /// Takes the text set on the options, followed by `text`
+    fn take_text(&mut self, text: Vec<String>) -> Vec<String> {
+        let mut all = self.text.take().unwrap_or_default();
+        all.extend(text);
+        all
+    }
+
     /// Creates a map of request params from an instance of `TextOptions`
-    fn into_form(self) -> Vec<(&'static str, String)> {
+    fn to_form(&self) -> Vec<(&'static str, String)> {
         let mut form = vec![];
 
         form.push(("target_lang", self.target_lang.to_string()));
 
         if let Some(src) = self.source_lang {
-            form.push(("source_lang", src.as_ref().to_string()));
+            // DeepL only accepts base languages as source, e.g. `EN` rather than `EN-GB`
+            form.push(("source_lang", src.base().to_string()));
         }
-        if let Some(ss) = self.split_sentences {
+        // with tag handling, DeepL recommends not splitting on newlines
+        let split_sentences = match (self.split_sentences, self.effective_tag_handling()) {
+            (None, Some(_)) => Some(SplitSentences::NoNewlines),
+            (ss, _) => ss,
+        };
+        if let Some(ss) = split_sentences {
             form.push(("split_sentences", ss.as_ref().to_string()));
         }
         if let Some(pf) = self.preserve_formatting {
@@ -140,32 +566,292 @@ impl TextOptions {
                 form.push(("preserve_formatting", "1".to_string()));
             }
         }
-        if let Some(fm) = self.formality {
+        if let Some(fm) = self
+            .formality
+            .and_then(|fm| fm.for_target(self.target_lang))
+        {
             form.push(("formality", fm.as_ref().to_string()));
         }
-        if let Some(g) = self.glossary_id {
-            form.push(("glossary_id", g));
+        if let Some(g) = &self.glossary_id {
+            form.push(("glossary_id", g.clone()));
         }
-        if let Some(th) = self.tag_handling {
+        if let Some(th) = self.effective_tag_handling() {
             form.push(("tag_handling", th.as_ref().to_string()));
         }
-        if let Some(non) = self.non_splitting_tags {
-            form.push(("non_splitting_tags", non));
+        if let Some(non) = &self.non_splitting_tags {
+            form.push(("non_splitting_tags", non.clone()));
         }
         if let Some(od) = self.outline_detection {
             if !od {
                 form.push(("outline_detection", "0".to_string()));
             }
         }
-        if let Some(sp) = self.splitting_tags {
-            form.push(("splitting_tags", sp));
+        if let Some(sp) = &self.splitting_tags {
+            form.push(("splitting_tags", sp.clone()));
+        }
+        match (&self.ignore_tags, self.protects_terms()) {
+            (Some(ig), true) => form.push(("ignore_tags", format!("{ig},{PROTECT_TAG}"))),
+            (Some(ig), false) => form.push(("ignore_tags", ig.clone())),
+            (None, true) => form.push(("ignore_tags", PROTECT_TAG.to_string())),
+            (None, false) => {}
         }
-        if let Some(ig) = self.ignore_tags {
-            form.push(("ignore_tags", ig));
+        if let Some(mt) = self.model_type {
+            form.push(("model_type", mt.as_ref().to_string()));
         }
 
         form
     }
+
+    /// Creates the request params for translating `text` with these options
+    fn to_params(&self, text: &[String]) -> Vec<(&'static str, String)> {
+        let mut params = self.to_form();
+        for t in text {
+            params.push(("text", t.clone()));
+        }
+        params
+    }
+}
+
+/// Echoes `text` as its own translations, see [`DeepL::set_dry_run`]
+fn dry_run_result(opt: &TextOptions, text: Vec<String>) -> TranslateTextResult {
+    let source = opt.source_lang.map(|l| l.base().to_string());
+
+    let translations = text
+        .into_iter()
+        .map(|t| Translation {
+            detected_source_language: source.clone().unwrap_or_default(),
+            text: t.clone(),
+            source_text: Some(t),
+        })
+        .collect();
+
+    TranslateTextResult {
+        translations,
+        model_type_used: None,
+    }
+}
+
+/// Maximum number of texts DeepL accepts in a single translate request
+const MAX_TEXTS: usize = 50;
+
+/// Checks that `target` may be used as a target language.
+///
+/// The source language needs no check: the only target-only languages are regional
+/// variants such as `EN-GB`, which are sent as their base language instead.
+fn validate_target_lang(target: Language) -> Result<()> {
+    if target.is_source_only() {
+        return Err(Error::Client(format!(
+            "{target} cannot be used as a target language"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Counts characters the way DeepL bills them, i.e. by Unicode code points
+fn count_characters(text: &[String]) -> u64 {
+    text.iter().map(|t| t.chars().count() as u64).sum()
+}
+
+/// Joins tag names into the comma-separated list DeepL expects
+fn join_tags<I, S>(tags: I) -> String
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    tags.into_iter()
+        .map(Into::into)
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Maximum number of words in a source text for its case to be mirrored onto the
+/// translation, see [`TextOptions::match_source_case`]
+const MATCH_CASE_MAX_WORDS: usize = 3;
+
+/// Heuristically mirrors the capitalization of a short `source` onto `text`: an
+/// all-caps source gives an all-caps translation, otherwise the case of the first
+/// letter is copied. Longer sources are left alone.
+fn match_case(source: &str, text: &str) -> String {
+    if source.split_whitespace().count() > MATCH_CASE_MAX_WORDS {
+        return text.to_string();
+    }
+
+    let letters = || source.chars().filter(|c| c.is_alphabetic());
+    if letters().count() > 1 && letters().all(char::is_uppercase) {
+        return text.to_uppercase();
+    }
+
+    let (Some(first), Some(c)) = (letters().next(), text.chars().next()) else {
+        return text.to_string();
+    };
+    let rest = &text[c.len_utf8()..];
+    if first.is_uppercase() {
+        c.to_uppercase().chain(rest.chars()).collect()
+    } else if first.is_lowercase() {
+        c.to_lowercase().chain(rest.chars()).collect()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Maximum number of characters translated to detect a language
+const DETECT_SAMPLE_LEN: usize = 100;
+
+/// HTML elements which never have a closing tag
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Name of the ignored XML tag wrapping protected terms, see [`TextOptions::protect_terms`]
+const PROTECT_TAG: &str = "x";
+
+/// Escapes `text` for XML and wraps each occurrence of one of `terms` in a
+/// [`PROTECT_TAG`] element. At each position the longest matching term is taken,
+/// and matches don't overlap.
+fn protect(text: &str, terms: &[String]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < text.len() {
+        let rest = &text[i..];
+        let term = terms
+            .iter()
+            .filter(|t| rest.starts_with(t.as_str()))
+            .max_by_key(|t| t.len());
+
+        match term {
+            Some(term) => {
+                out.push_str(&format!("<{PROTECT_TAG}>"));
+                out.push_str(&xml_escape(term));
+                out.push_str(&format!("</{PROTECT_TAG}>"));
+                i += term.len();
+            }
+            None => {
+                let c = rest.chars().next().unwrap_or_default();
+                out.push_str(&xml_escape(c.encode_utf8(&mut [0; 4])));
+                i += c.len_utf8();
+            }
+        }
+    }
+
+    out
+}
+
+/// Reverses [`protect`], removing the wrapping tags and unescaping the text
+fn unprotect(text: &str) -> String {
+    let text = text
+        .replace(&format!("<{PROTECT_TAG}>"), "")
+        .replace(&format!("</{PROTECT_TAG}>"), "");
+    xml_unescape(&text)
+}
+
+/// Escapes the characters with special meaning in XML text
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Rewrites HTML void elements in `text`, e.g. `<br>`, as self-closing XHTML tags
+/// (`<br/>`) if `close` is true, or self-closing void elements back to plain HTML
+/// if false. Other tags and comments are left untouched.
+fn rewrite_void_tags(text: &str, close: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('<') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = if rest.starts_with("<!--") {
+            rest.find("-->").map(|i| i + 2)
+        } else {
+            rest.find('>')
+        };
+        let Some(end) = end else {
+            break;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        let name = tag
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        if !VOID_ELEMENTS.contains(&name.as_str()) {
+            out.push('<');
+            out.push_str(tag);
+            out.push('>');
+            continue;
+        }
+
+        let inner = tag.strip_suffix('/').map_or(tag, str::trim_end);
+        out.push('<');
+        out.push_str(inner);
+        out.push_str(if close { "/>" } else { ">" });
+    }
+    out.push_str(rest);
+
+    out
+}
+
+/// Lightweight well-formedness check asserting every opening tag in `text` is matched
+/// by a closing tag in the right order. Comments, processing instructions, declarations,
+/// self-closing tags and (for HTML) void elements are skipped.
+fn tags_balanced(text: &str, kind: TagHandling) -> bool {
+    let mut stack: Vec<String> = vec![];
+    let mut rest = text;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        let Some(end) = rest.find('>') else {
+            return false;
+        };
+        let tag = &rest[1..end];
+
+        if tag.starts_with("!--") {
+            // comments may contain '>', so skip to the real end
+            let Some(close) = rest.find("-->") else {
+                return false;
+            };
+            rest = &rest[close + 3..];
+            continue;
+        }
+        rest = &rest[end + 1..];
+
+        if tag.starts_with('?') || tag.starts_with('!') || tag.ends_with('/') {
+            continue;
+        }
+
+        let (closing, tag) = match tag.strip_prefix('/') {
+            Some(t) => (true, t),
+            None => (false, tag),
+        };
+        let name = tag.split_whitespace().next().unwrap_or_default();
+        let name = match kind {
+            TagHandling::Html => name.to_lowercase(),
+            TagHandling::Xml => name.to_string(),
+        };
+        if name.is_empty() {
+            return false;
+        }
+        if matches!(kind, TagHandling::Html) && VOID_ELEMENTS.contains(&name.as_str()) {
+            continue;
+        }
+
+        if closing {
+            if stack.pop() != Some(name) {
+                return false;
+            }
+        } else {
+            stack.push(name);
+        }
+    }
+
+    stack.is_empty()
 }
 
 impl DeepL {
@@ -174,6 +860,7 @@ impl DeepL {
     /// Translate one or more text strings.
     ///
     /// To translate text all we need is to specify a target language and a chunk of text to translate.
+    /// The text may be passed directly or set on the options with [`TextOptions::texts`].
     /// In addition, the [`TextOptions`] type exposes a number of methods used to control formatting,
     /// set a desired formality, or tell the server how to handle HTML or XML tags.
     ///
@@ -211,30 +898,466 @@ impl DeepL {
     /// let res = dl.translate(opt, text).unwrap();
     /// assert!(!res.translations.is_empty());
     /// ```
+    ///
+    /// Setting `verify_tags` together with `tag_handling` checks that the tags in each
+    /// translation are still balanced, returning an error if they aren't.
+    ///
+    /// A formality of [`Formality::More`] or [`Formality::Less`] is not sent for a target
+    /// language that doesn't support formality, which DeepL would reject, see
+    /// [`Language::supports_formality`]. The `Prefer*` variants are always sent.
+    ///
+    /// When `tag_handling` is set and `split_sentences` is not, `split_sentences` is sent as
+    /// [`SplitSentences::NoNewlines`], as recommended by DeepL for tagged text. Set
+    /// `split_sentences` explicitly to override this.
+    ///
     /// ## Errors
     ///
-    /// If target language and (optionally provided) source language are an invalid pair.
-    pub fn translate(&self, opt: TextOptions, text: Vec<String>) -> Result<TranslateTextResult> {
+    /// If the target language can't be used as a target. For instance, a source-only
+    /// language such as `EN` used as the target is rejected before sending the request,
+    /// unless disabled with [`validate_languages(false)`](TextOptions::validate_languages).
+    /// A regional source language such as `EN-GB` is not rejected, but sent as its base
+    /// language.
+    ///
+    /// If any of `splitting_tags`, `non_splitting_tags`, `ignore_tags`, or
+    /// `outline_detection` is set without `tag_handling`, since DeepL would silently
+    /// ignore them.
+    ///
+    /// If the server returns a different number of translations than texts sent, an
+    /// [`Error::TranslationCount`] is returned.
+    pub fn translate(
+        &self,
+        mut opt: TextOptions,
+        text: Vec<String>,
+    ) -> Result<TranslateTextResult> {
+        opt.validate()?;
+        let text = opt.take_text(text);
         if text.is_empty() || text[0].is_empty() {
             return Err(Error::Client("empty text parameter".to_string()));
         }
-        let url = format!("{}/translate", self.url);
-        let mut params = opt.into_form();
+        self.check_budget(&text)?;
 
-        for t in text {
-            params.push(("text", t));
+        self.translate_chunk(&opt, text)
+    }
+
+    /// Like [`translate`](Self::translate), but skips translations in the response that
+    /// can't be deserialized instead of failing the whole call, which may salvage a large
+    /// batch. The number of skipped translations is reported in the result.
+    ///
+    /// As with [`translate_batched`](Self::translate_batched), the text is sent in chunks
+    /// of at most 50 texts. The `source_text` of each valid translation is attached
+    /// according to its position in the response.
+    pub fn translate_lenient(
+        &self,
+        mut opt: TextOptions,
+        text: Vec<String>,
+    ) -> Result<LenientTranslateResult> {
+        opt.validate()?;
+        let text = opt.take_text(text);
+        if text.is_empty() || text[0].is_empty() {
+            return Err(Error::Client("empty text parameter".to_string()));
+        }
+        self.check_budget(&text)?;
+
+        let mut translations = Vec::with_capacity(text.len());
+        let mut skipped = 0;
+        let mut text = text.into_iter().peekable();
+
+        while text.peek().is_some() {
+            let chunk: Vec<String> = text.by_ref().take(MAX_TEXTS).collect();
+            let read = |resp: reqwest::blocking::Response| {
+                let result: LenientResponse = resp.json().map_err(Error::deserialize)?;
+                Ok(result
+                    .translations
+                    .into_iter()
+                    .map(|t| match t {
+                        MaybeTranslation::Valid(t) => Some(t),
+                        MaybeTranslation::Malformed(_) => None,
+                    })
+                    .collect())
+            };
+
+            for t in self.translate_chunk_with(&opt, chunk, read)? {
+                match t {
+                    Some(t) => translations.push(t),
+                    None => skipped += 1,
+                }
+            }
+        }
+
+        Ok(LenientTranslateResult {
+            translations,
+            skipped,
+        })
+    }
+
+    /// Translate one or more text strings to the default target language set with
+    /// [`set_default_target`](Self::set_default_target).
+    ///
+    /// ## Errors
+    ///
+    /// If no default target language is set.
+    pub fn translate_default(&self, text: Vec<String>) -> Result<TranslateTextResult> {
+        let target = self
+            .default_target
+            .ok_or_else(|| Error::Client("no default target language set".to_string()))?;
+
+        self.translate(TextOptions::new(target), text)
+    }
+
+    /// Translate one or more text strings, returning only the translated texts in input order.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::*;
+    /// # let dl = DeepL::new(&std::env::var("DEEPL_API_KEY").unwrap());
+    /// let text = vec!["good morning".to_string(), "good night".to_string()];
+    /// let texts = dl.translate_texts_only(TextOptions::new(Language::DE), text).unwrap();
+    /// assert_eq!(texts, ["Guten Morgen", "Gute Nacht"]);
+    /// ```
+    pub fn translate_texts_only(&self, opt: TextOptions, text: Vec<String>) -> Result<Vec<String>> {
+        let result = self.translate(opt, text)?;
+
+        Ok(result.translations.into_iter().map(|t| t.text).collect())
+    }
+
+    /// Translate any number of text strings.
+    ///
+    /// DeepL accepts at most 50 texts per request, so `texts` is split into chunks which are
+    /// sent one after another using the same options. Translations are returned in input order.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the first error encountered, in which case no translations are returned.
+    pub fn translate_batched(
+        &self,
+        mut opt: TextOptions,
+        texts: Vec<String>,
+    ) -> Result<Vec<Translation>> {
+        opt.validate()?;
+        let texts = opt.take_text(texts);
+        self.check_budget(&texts)?;
+
+        let mut translations = Vec::with_capacity(texts.len());
+        let mut texts = texts.into_iter().peekable();
+
+        while texts.peek().is_some() {
+            let chunk: Vec<String> = texts.by_ref().take(MAX_TEXTS).collect();
+            let result = self.translate_chunk(&opt, chunk)?;
+            translations.extend(result.translations);
+        }
+
+        Ok(translations)
+    }
+
+    /// Detect the language of a text.
+    ///
+    /// DeepL has no endpoint for language detection, so this translates a sample of at most
+    /// 100 characters of `text` and returns the detected source language. Note the sample
+    /// is billed against your character quota like any other translation.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::*;
+    /// # let dl = DeepL::new(&std::env::var("DEEPL_API_KEY").unwrap());
+    /// let lang = dl.detect_language("Guten Morgen").unwrap();
+    /// assert_eq!(lang, Language::DE);
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`Error::InvalidLanguage`] if the detected language isn't modeled by this crate.
+    pub fn detect_language(&self, text: &str) -> Result<Language> {
+        let sample: String = text.chars().take(DETECT_SAMPLE_LEN).collect();
+        let result = self.translate(TextOptions::new(Language::DE), vec![sample])?;
+
+        let translation = result.translations.first().ok_or(Error::InvalidResponse)?;
+
+        translation.detected_source_language.parse()
+    }
+
+    /// Rejects text exceeding the configured maximum characters per call
+    fn check_budget(&self, text: &[String]) -> Result<()> {
+        let Some(max) = self.max_characters else {
+            return Ok(());
+        };
+
+        let count = count_characters(text);
+        if count > max {
+            return Err(Error::Client(format!(
+                "text contains {count} characters, exceeding the maximum of {max} per call"
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Sends a single translate request
+    fn translate_chunk(&self, opt: &TextOptions, text: Vec<String>) -> Result<TranslateTextResult> {
+        let mut model_type_used = None;
+        let read = |resp: reqwest::blocking::Response| {
+            let result: TranslateTextResult = resp.json().map_err(Error::deserialize)?;
+            model_type_used = result.model_type_used;
+            Ok(result.translations.into_iter().map(Some).collect())
+        };
+        let translations = self.translate_chunk_with(opt, text, read)?;
+
+        Ok(TranslateTextResult {
+            translations: translations.into_iter().flatten().collect(),
+            model_type_used,
+        })
+    }
+
+    /// Sends a single translate request, reading the translations in the response with
+    /// `read`, which gives `None` for a translation it can't read. The translations are
+    /// checked and restored to match the input text.
+    fn translate_chunk_with<F>(
+        &self,
+        opt: &TextOptions,
+        text: Vec<String>,
+        read: F,
+    ) -> Result<Vec<Option<Translation>>>
+    where
+        F: FnOnce(reqwest::blocking::Response) -> Result<Vec<Option<Translation>>>,
+    {
+        if self.dry_run {
+            let result = dry_run_result(opt, text);
+            return Ok(result.translations.into_iter().map(Some).collect());
+        }
+
+        let verify = match opt.effective_tag_handling() {
+            Some(kind) if opt.verify_tags == Some(true) => Some(kind),
+            _ => None,
+        };
+
+        // text is rewritten for protected terms or void tags, and restored in the output
+        let prepared = opt.prepare_text(&text);
+        let resp = self.translate_send(opt, prepared.as_deref().unwrap_or(&text))?;
+        let mut translations = read(resp)?;
+
+        // guard against misaligning translations with their input
+        if translations.len() != text.len() {
+            return Err(Error::TranslationCount(text.len(), translations.len()));
         }
 
-        let resp = self
-            .post(url)
-            .form(&params)
-            .send()
-            .map_err(Error::Reqwest)?;
+        if let Some(kind) = verify {
+            if let Some(t) = translations
+                .iter()
+                .flatten()
+                .find(|t| !tags_balanced(&t.text, kind))
+            {
+                return Err(Error::Client(format!(
+                    "translation contains unbalanced tags: {}",
+                    t.text
+                )));
+            }
+        }
 
-        if !resp.status().is_success() {
-            return super::convert(resp);
+        // attach the original input to each translation
+        for (translation, source) in translations.iter_mut().zip(text) {
+            let Some(translation) = translation else {
+                continue;
+            };
+            if opt.protects_terms() {
+                translation.text = unprotect(&translation.text);
+            } else if opt.normalizes_void_tags() {
+                translation.text = rewrite_void_tags(&translation.text, false);
+            }
+            if opt.match_source_case == Some(true) {
+                translation.text = match_case(&source, &translation.text);
+            }
+            translation.source_text = Some(source);
         }
 
-        resp.json().map_err(|_| Error::Deserialize)
+        Ok(translations)
+    }
+
+    /// Sends a translate request, retrying transient failures, and returns the
+    /// successful response
+    fn translate_send(
+        &self,
+        opt: &TextOptions,
+        text: &[String],
+    ) -> Result<reqwest::blocking::Response> {
+        let url = self.endpoint(&["translate"]);
+        let params = opt.to_params(text);
+
+        self.retry(Error::is_retryable, || {
+            let resp = self.send(self.post(url.clone()).form(&params))?;
+
+            if !resp.status().is_success() {
+                return super::convert(resp);
+            }
+
+            Ok(resp)
+        })
+    }
+
+    /// Translate one or more text strings, keeping each of `terms` verbatim in the
+    /// translations. See [`TextOptions::protect_terms`] for how terms are matched.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::*;
+    /// # let dl = DeepL::new(&std::env::var("DEEPL_API_KEY").unwrap());
+    /// let text = vec!["Install deeprl with cargo add deeprl".to_string()];
+    /// let res = dl
+    ///     .translate_protected(text, Language::DE, &["deeprl", "cargo add"])
+    ///     .unwrap();
+    /// assert!(res.translations[0].text.contains("cargo add deeprl"));
+    /// ```
+    pub fn translate_protected(
+        &self,
+        text: Vec<String>,
+        target: Language,
+        terms: &[&str],
+    ) -> Result<TranslateTextResult> {
+        let opt = TextOptions::new(target).protect_terms(terms);
+        self.translate(opt, text)
+    }
+
+    /// Translate one or more text strings using a glossary, with the source and target
+    /// languages taken from the glossary's language pair.
+    ///
+    /// ## Errors
+    ///
+    /// If the glossary can't be fetched, or if its target language is only valid as a source
+    /// language, i.e. `EN` or `PT`. For those, set the regional variant, e.g. `EN-GB`, along
+    /// with the `glossary_id` on [`TextOptions`] and call [`translate`](Self::translate).
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::*;
+    /// # let dl = DeepL::new(&std::env::var("DEEPL_API_KEY").unwrap());
+    /// # let glossary_id = "def3a26b-3e84-45b3-84ae-0c0aaf3525f7";
+    /// let text = vec!["hello".to_string()];
+    /// let result = dl.translate_with_glossary(glossary_id, text).unwrap();
+    /// ```
+    pub fn translate_with_glossary(
+        &self,
+        glossary_id: &str,
+        text: Vec<String>,
+    ) -> Result<TranslateTextResult> {
+        let (source, target) = self.glossary_info(glossary_id)?.language_pair()?;
+
+        let opt = TextOptions::new(target)
+            .source_lang(source)
+            .glossary_id(glossary_id.to_string());
+
+        self.translate(opt, text)
+    }
+
+    /// Translate the same text strings into each of `targets`, sending one request per
+    /// target language.
+    ///
+    /// Every request uses the options in `opt`, e.g. the source language, formality or
+    /// tag handling, with only the target language replaced by each of `targets`.
+    /// A failed target doesn't abort the others, so the result holds the outcome of each
+    /// target keyed by language.
+    ///
+    /// ## Errors
+    ///
+    /// If there is no text, in which case no requests are sent.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::*;
+    /// # let dl = DeepL::new(&std::env::var("DEEPL_API_KEY").unwrap());
+    /// let opt = TextOptions::new(Language::DE)
+    ///     .source_lang(Language::EN)
+    ///     .formality(Formality::PreferLess);
+    /// let text = vec!["good morning".to_string()];
+    /// let targets = [Language::DE, Language::FR, Language::ES];
+    /// let results = dl.translate_multi(opt, text, &targets).unwrap();
+    ///
+    /// for (lang, result) in results {
+    ///     match result {
+    ///         Ok(translations) => println!("{lang}: {}", translations[0].text),
+    ///         Err(e) => println!("{lang}: {e}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn translate_multi(
+        &self,
+        mut opt: TextOptions,
+        text: Vec<String>,
+        targets: &[Language],
+    ) -> Result<HashMap<Language, Result<Vec<Translation>>>> {
+        let text = opt.take_text(text);
+        if text.is_empty() || text[0].is_empty() {
+            return Err(Error::Client("empty text parameter".to_string()));
+        }
+
+        let results = targets
+            .iter()
+            .map(|&target| {
+                let mut opt = opt.clone();
+                opt.target_lang = target;
+                let result = self.translate(opt, text.clone()).map(|r| r.translations);
+                (target, result)
+            })
+            .collect();
+
+        Ok(results)
+    }
+
+    /// Translate a single text string to the target language, returning only the translated text.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::*;
+    /// # let dl = DeepL::new(&std::env::var("DEEPL_API_KEY").unwrap());
+    /// let text = dl.translate_string("good morning", Language::DE).unwrap();
+    /// assert_eq!(text, "Guten Morgen");
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`Error::InvalidResponse`] if the response doesn't contain exactly one translation.
+    pub fn translate_string(&self, text: &str, target: Language) -> Result<String> {
+        let opt = TextOptions::new(target);
+        let mut result = self
+            .translate(opt, vec![text.to_string()])
+            .map_err(|e| match e {
+                Error::TranslationCount(..) => Error::InvalidResponse,
+                e => e,
+            })?;
+
+        // `translate` checked that there is exactly one translation
+        Ok(result.translations.swap_remove(0).text)
+    }
+
+    /// Translate the text set on the options and write the translations to a file at
+    /// `path`, one per line, replacing the file if it exists.
+    ///
+    /// ## Errors
+    ///
+    /// If the translation fails, or [`Error::Io`] if the file can't be written.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// # use deeprl::*;
+    /// # use std::path::Path;
+    /// # let dl = DeepL::new(&std::env::var("DEEPL_API_KEY").unwrap());
+    /// let opt = TextOptions::new(Language::DE).texts(["good morning", "good night"]);
+    /// dl.translate_to_file(opt, Path::new("greetings.txt")).unwrap();
+    /// ```
+    pub fn translate_to_file(&self, opt: TextOptions, path: &Path) -> Result<()> {
+        let result = self.translate(opt, vec![])?;
+
+        let content = result
+            .translations
+            .into_iter()
+            .map(|t| t.text)
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Ok(std::fs::write(path, content)?)
     }
 }
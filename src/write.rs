@@ -0,0 +1,188 @@
+use serde::Deserialize;
+
+use super::{Error, Result};
+use crate::{builder, DeepL, Language};
+
+/// Sets the style in which text should be rephrased
+#[derive(Copy, Clone, Debug)]
+pub enum WritingStyle {
+    /// Default style
+    Default,
+    /// Simple style
+    Simple,
+    /// Business style
+    Business,
+    /// Academic style
+    Academic,
+    /// Casual style
+    Casual,
+    /// Simple style if supported by target language, else default
+    PreferSimple,
+    /// Business style if supported by target language, else default
+    PreferBusiness,
+    /// Academic style if supported by target language, else default
+    PreferAcademic,
+    /// Casual style if supported by target language, else default
+    PreferCasual,
+}
+
+/// Sets the tone in which text should be rephrased
+#[derive(Copy, Clone, Debug)]
+pub enum Tone {
+    /// Default tone
+    Default,
+    /// Enthusiastic tone
+    Enthusiastic,
+    /// Friendly tone
+    Friendly,
+    /// Confident tone
+    Confident,
+    /// Diplomatic tone
+    Diplomatic,
+    /// Enthusiastic tone if supported by target language, else default
+    PreferEnthusiastic,
+    /// Friendly tone if supported by target language, else default
+    PreferFriendly,
+    /// Confident tone if supported by target language, else default
+    PreferConfident,
+    /// Diplomatic tone if supported by target language, else default
+    PreferDiplomatic,
+}
+
+/// An individual improvement
+#[derive(Debug, Deserialize)]
+pub struct Improvement {
+    /// Rephrased text
+    pub text: String,
+    /// Language of the rephrased text
+    pub target_language: String,
+    /// Detected source language
+    #[serde(default)]
+    pub detected_source_language: Option<String>,
+}
+
+/// Rephrase result
+#[derive(Debug, Deserialize)]
+pub struct RephraseResult {
+    /// List of improvements
+    pub improvements: Vec<Improvement>,
+}
+
+impl AsRef<str> for WritingStyle {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Default => "default",
+            Self::Simple => "simple",
+            Self::Business => "business",
+            Self::Academic => "academic",
+            Self::Casual => "casual",
+            Self::PreferSimple => "prefer_simple",
+            Self::PreferBusiness => "prefer_business",
+            Self::PreferAcademic => "prefer_academic",
+            Self::PreferCasual => "prefer_casual",
+        }
+    }
+}
+
+impl AsRef<str> for Tone {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Default => "default",
+            Self::Enthusiastic => "enthusiastic",
+            Self::Friendly => "friendly",
+            Self::Confident => "confident",
+            Self::Diplomatic => "diplomatic",
+            Self::PreferEnthusiastic => "prefer_enthusiastic",
+            Self::PreferFriendly => "prefer_friendly",
+            Self::PreferConfident => "prefer_confident",
+            Self::PreferDiplomatic => "prefer_diplomatic",
+        }
+    }
+}
+
+// WriteOptions builder
+builder! {
+    Write {
+        @must{
+            target_lang: Language,
+        };
+        @optional{
+            writing_style: WritingStyle,
+            tone: Tone,
+        };
+        @list{
+            text: String,
+        };
+    }
+}
+
+impl WriteOptions {
+    /// Creates a list of form parameters from the options, excluding text
+    fn to_form(&self) -> Vec<(&'static str, String)> {
+        let mut form = vec![];
+
+        form.push(("target_lang", self.target_lang.to_string()));
+
+        if let Some(style) = self.writing_style {
+            form.push(("writing_style", style.as_ref().to_string()));
+        }
+        if let Some(tone) = self.tone {
+            form.push(("tone", tone.as_ref().to_string()));
+        }
+
+        form
+    }
+}
+
+impl DeepL {
+    /// POST /write/rephrase
+    ///
+    /// Rephrase one or more text strings, optionally in a given writing style or tone.
+    ///
+    /// ## Errors
+    ///
+    /// If no text is given, or if both a writing style and a tone are set, as DeepL
+    /// accepts at most one of them per request.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use deeprl::{DeepL, Language, WriteOptions, WritingStyle};
+    ///
+    /// let dl = DeepL::new(&std::env::var("DEEPL_API_KEY").unwrap());
+    ///
+    /// let opt = WriteOptions::new(Language::ENUS)
+    ///     .writing_style(WritingStyle::Business)
+    ///     .text(vec!["i need the report asap".to_string()]);
+    ///
+    /// let result = dl.rephrase(opt).unwrap();
+    /// println!("{}", result.improvements[0].text);
+    /// ```
+    pub fn rephrase(&self, opt: WriteOptions) -> Result<RephraseResult> {
+        if opt.writing_style.is_some() && opt.tone.is_some() {
+            return Err(Error::Client(
+                "writing style and tone cannot both be set".to_string(),
+            ));
+        }
+
+        let text = match &opt.text {
+            Some(text) if !text.is_empty() => text,
+            _ => return Err(Error::Client("empty text parameter".to_string())),
+        };
+
+        let url = self.endpoint(&["write", "rephrase"]);
+        let mut params = opt.to_form();
+
+        for t in text {
+            params.push(("text", t.clone()));
+        }
+
+        let resp = self.send(self.post(url).form(&params))?;
+
+        if !resp.status().is_success() {
+            return super::convert(resp);
+        }
+
+        resp.json().map_err(Error::deserialize)
+    }
+}